@@ -0,0 +1,257 @@
+// Copyright (c) 2026 Mikko Tanner. All rights reserved.
+// Licensed under the MIT License or the Apache License, Version 2.0.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! The crate-wide error type.
+
+#[cfg(feature = "iptools")]
+use crate::iptools::{AddressError, ExprError, IpSetError, IpamError, MacError, VlsmError};
+#[cfg(feature = "std")]
+use alloc::string::String;
+#[cfg(feature = "std")]
+use core::num::ParseIntError;
+use core::{error, fmt};
+#[cfg(feature = "std")]
+use std::{io, path::PathBuf};
+
+/**
+Any error returned by this crate, one or more variants per module.
+
+The [iptools](crate::iptools) error types convert into this with `?` and
+display as themselves. Messages never include their cause: walk
+[source](error::Error::source) (or use a chain printer) for the underlying
+error where there is one. More variants may be added, so matches
+need a wildcard arm.
+*/
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum Error {
+    /// [to_human](crate::HumanBytes::to_human): NaN, infinite or subnormal number
+    #[cfg(feature = "std")]
+    HumanNumber(f64),
+    /// [to_human](crate::HumanBytes::to_human): precision not in range 0-3
+    #[cfg(feature = "std")]
+    HumanPrecision(usize),
+    /// [str_to_bytes](crate::str_to_bytes): not a number with a known unit
+    #[cfg(feature = "std")]
+    ByteSize {
+        input: String,
+        source: ParseIntError,
+    },
+    /// [str_to_bytes_64](crate::str_to_bytes_64): the size does not fit in a `u64`
+    #[cfg(feature = "std")]
+    ByteOverflow(u128),
+    /// [check_readable_dir](crate::check_readable_dir): the path does not exist
+    #[cfg(feature = "std")]
+    DirNotFound(PathBuf),
+    /// [check_readable_dir](crate::check_readable_dir): the path is not a directory
+    #[cfg(feature = "std")]
+    NotADir(PathBuf),
+    /// reading metadata of or resolving a path failed
+    #[cfg(feature = "std")]
+    Io { path: PathBuf, source: io::Error },
+    #[cfg(feature = "iptools")]
+    Address(AddressError),
+    #[cfg(feature = "iptools")]
+    IpSet(IpSetError),
+    #[cfg(feature = "iptools")]
+    Expr(ExprError),
+    #[cfg(feature = "iptools")]
+    Ipam(IpamError),
+    #[cfg(feature = "iptools")]
+    Vlsm(VlsmError),
+    #[cfg(feature = "iptools")]
+    Mac(MacError),
+    #[cfg(all(feature = "iptools", feature = "std"))]
+    External(crate::iptools::ExternalError),
+    #[cfg(feature = "cloud")]
+    Cloud(crate::iptools::CloudError),
+    #[cfg(all(feature = "iptools", feature = "tabulate"))]
+    Map(crate::iptools::MapError),
+    /// [compile_regex](crate::iptools::compile_regex)
+    #[cfg(all(feature = "iptools", feature = "regex"))]
+    Regex(regex::Error),
+}
+
+impl fmt::Display for Error {
+    #[cfg_attr(
+        not(any(feature = "std", feature = "iptools")),
+        allow(unused_variables)
+    )]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            Error::HumanNumber(num) => write!(f, "num must be a regular number, got {num}"),
+            #[cfg(feature = "std")]
+            Error::HumanPrecision(p) => write!(f, "precision must be in range 0-3, got {p}"),
+            #[cfg(feature = "std")]
+            Error::ByteSize { input, .. } => write!(f, "invalid size '{input}'"),
+            #[cfg(feature = "std")]
+            Error::ByteOverflow(n) => write!(f, "size too large to fit in a u64: {n}"),
+            #[cfg(feature = "std")]
+            Error::DirNotFound(path) => write!(f, "Directory {} does not exist", path.display()),
+            #[cfg(feature = "std")]
+            Error::NotADir(path) => write!(f, "Not a directory: {}", path.display()),
+            #[cfg(feature = "std")]
+            Error::Io { path, .. } => write!(f, "Failed to access {}", path.display()),
+            #[cfg(feature = "iptools")]
+            Error::Address(e) => write!(f, "{e}"),
+            #[cfg(feature = "iptools")]
+            Error::IpSet(e) => write!(f, "{e}"),
+            #[cfg(feature = "iptools")]
+            Error::Expr(e) => write!(f, "{e}"),
+            #[cfg(feature = "iptools")]
+            Error::Ipam(e) => write!(f, "{e}"),
+            #[cfg(feature = "iptools")]
+            Error::Vlsm(e) => write!(f, "{e}"),
+            #[cfg(feature = "iptools")]
+            Error::Mac(e) => write!(f, "{e}"),
+            #[cfg(all(feature = "iptools", feature = "std"))]
+            Error::External(e) => write!(f, "{e}"),
+            #[cfg(feature = "cloud")]
+            Error::Cloud(e) => write!(f, "{e}"),
+            #[cfg(all(feature = "iptools", feature = "tabulate"))]
+            Error::Map(e) => write!(f, "{e}"),
+            #[cfg(all(feature = "iptools", feature = "regex"))]
+            Error::Regex(e) => write!(f, "{e}"),
+            // no variants without features
+            #[cfg(not(any(feature = "std", feature = "iptools")))]
+            _ => Ok(()),
+        }
+    }
+}
+
+impl error::Error for Error {
+    /// The wrapped module errors are transparent: their message is this
+    /// error's message, so the chain continues with their own source.
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "std")]
+            Error::ByteSize { source, .. } => Some(source),
+            #[cfg(feature = "std")]
+            Error::Io { source, .. } => Some(source),
+            #[cfg(feature = "iptools")]
+            Error::Address(e) => e.source(),
+            #[cfg(feature = "iptools")]
+            Error::IpSet(e) => e.source(),
+            #[cfg(feature = "iptools")]
+            Error::Expr(e) => e.source(),
+            #[cfg(feature = "iptools")]
+            Error::Ipam(e) => e.source(),
+            #[cfg(feature = "iptools")]
+            Error::Vlsm(e) => e.source(),
+            #[cfg(feature = "iptools")]
+            Error::Mac(e) => e.source(),
+            #[cfg(all(feature = "iptools", feature = "std"))]
+            Error::External(e) => e.source(),
+            #[cfg(feature = "cloud")]
+            Error::Cloud(e) => e.source(),
+            #[cfg(all(feature = "iptools", feature = "tabulate"))]
+            Error::Map(e) => e.source(),
+            #[cfg(all(feature = "iptools", feature = "regex"))]
+            Error::Regex(e) => e.source(),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        }
+    }
+}
+
+/// `From` conversions for errors which map to a single variant.
+macro_rules! impl_from {
+    ($($(#[$cfg:meta])* $variant:ident($ty:ty),)+) => {$(
+        $(#[$cfg])*
+        impl From<$ty> for Error {
+            fn from(e: $ty) -> Self {
+                Error::$variant(e)
+            }
+        }
+    )+};
+}
+
+impl_from! {
+    #[cfg(feature = "iptools")] Address(AddressError),
+    #[cfg(feature = "iptools")] IpSet(IpSetError),
+    #[cfg(feature = "iptools")] Expr(ExprError),
+    #[cfg(feature = "iptools")] Ipam(IpamError),
+    #[cfg(feature = "iptools")] Vlsm(VlsmError),
+    #[cfg(feature = "iptools")] Mac(MacError),
+    #[cfg(all(feature = "iptools", feature = "std"))]
+    External(crate::iptools::ExternalError),
+    #[cfg(feature = "cloud")]
+    Cloud(crate::iptools::CloudError),
+    #[cfg(all(feature = "iptools", feature = "tabulate"))]
+    Map(crate::iptools::MapError),
+    #[cfg(all(feature = "iptools", feature = "regex"))]
+    Regex(regex::Error),
+}
+
+/* -------------------------------------------------------------------------- */
+
+#[cfg(all(test, feature = "std", feature = "iptools"))]
+mod tests {
+    use super::*;
+    use crate::{iptools::Cidr, str_to_bytes, str_to_bytes_64, HumanBytes};
+    use error::Error as _;
+
+    fn parse_cidr(s: &str) -> Result<Cidr, Error> {
+        Ok(s.parse::<Cidr>()?)
+    }
+
+    /// Messages of an error and its sources, checking that no link repeats its cause.
+    fn chain(err: &dyn error::Error) -> Vec<String> {
+        let mut chain: Vec<String> = vec![err.to_string()];
+        let mut cause: Option<&dyn error::Error> = err.source();
+        while let Some(e) = cause {
+            let msg: String = e.to_string();
+            assert!(!chain.last().unwrap().contains(&msg), "{chain:?} <- {msg}");
+            chain.push(msg);
+            cause = e.source();
+        }
+        chain
+    }
+
+    #[test]
+    fn test_conversions_and_sources() {
+        let err: Error = parse_cidr("10.0.0.0/x").unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Address(AddressError::InvalidCidrPrefix { .. })
+        ));
+        // Error (displayed as the AddressError) -> ParseIntError
+        assert!(err.source().unwrap().is::<ParseIntError>());
+        assert_eq!(
+            chain(&err),
+            [
+                "invalid prefix in CIDR: 'x'",
+                "invalid digit found in string"
+            ]
+        );
+        assert!(matches!(
+            parse_cidr("10.0.0.0/33"),
+            Err(Error::Address(AddressError::PrefixTooLong(_, 33)))
+        ));
+
+        assert!(matches!(str_to_bytes_64("64k"), Ok(65536)));
+        assert!(matches!(
+            str_to_bytes_64("1y"),
+            Err(Error::ByteOverflow(n)) if n == 1 << 80
+        ));
+        // the cause is no longer replaced with an overflow
+        let err: Error = str_to_bytes_64("lots").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            str_to_bytes("lots").unwrap_err().to_string()
+        );
+        assert!(err.source().is_some());
+        assert_eq!(chain(&err).len(), 2);
+
+        assert!(matches!(
+            HumanBytes::to_human(1.0, true, 4),
+            Err(Error::HumanPrecision(4))
+        ));
+        assert!(matches!(
+            crate::check_readable_dir(&"/nonexistent/miniutils".to_string()),
+            Err(Error::DirNotFound(_))
+        ));
+    }
+}
@@ -1,6 +1,7 @@
 // Copyright (c) 2024-2025 Mikko Tanner. All rights reserved.
 
-use std::{fs::metadata, io, path::Path};
+use crate::Error;
+use std::{fs::metadata, path::Path};
 
 use tracing::error;
 
@@ -14,29 +15,40 @@ Checks if the given directory path is readable.
 A canonicalized (absolute, resolved) path to the directory.
 
 ## Errors
-This function will return an error if the given path does not exist,
-is not a directory or if it fails to get metadata for the directory.
+This function will return [Error::DirNotFound] if the given path does not
+exist, [Error::NotADir] if it is not a directory, or [Error::Io] if it fails
+to get metadata for or to resolve the directory.
 */
-pub fn check_readable_dir(path: &String) -> Result<String, io::Error> {
+pub fn check_readable_dir(path: &String) -> Result<String, Error> {
     let path: &Path = Path::new(path);
+    let io_err = |source| Error::Io {
+        path: path.to_path_buf(),
+        source,
+    };
 
     if !path.exists() {
-        let errmsg: String = format!("Directory {} does not exist", path.display());
-        error!(errmsg);
-        return Err(io::Error::new(io::ErrorKind::NotFound, errmsg));
+        let err: Error = Error::DirNotFound(path.to_path_buf());
+        error!("{err}");
+        return Err(err);
     }
 
-    if let Ok(metadata) = metadata(path) {
-        if !metadata.is_dir() {
-            let errmsg: String = format!("Not a directory: {}", path.display());
-            error!(errmsg);
-            return Err(io::Error::new(io::ErrorKind::InvalidInput, errmsg));
+    match metadata(path) {
+        Ok(metadata) if !metadata.is_dir() => {
+            let err: Error = Error::NotADir(path.to_path_buf());
+            error!("{err}");
+            return Err(err);
+        }
+        Ok(_) => {}
+        Err(source) => {
+            error!("Failed to get metadata for: {}", path.display());
+            return Err(io_err(source));
         }
-    } else {
-        let errmsg: String = format!("Failed to get metadata for: {}", path.display());
-        error!(errmsg);
-        return Err(io::Error::new(io::ErrorKind::Other, errmsg));
     };
 
-    Ok(path.canonicalize()?.to_str().unwrap().to_string())
+    Ok(path
+        .canonicalize()
+        .map_err(io_err)?
+        .to_str()
+        .unwrap()
+        .to_string())
 }
@@ -1,5 +1,7 @@
 // Copyright (c) 2023 Mikko Tanner. All rights reserved.
 
+use crate::Error;
+
 /**
 The `HumanBytes` struct is a utility for converting a floating-point number
 to a human-readable string representation in either binary or metric units.
@@ -30,14 +32,15 @@ impl HumanBytes {
 
     # Returns
     * `Ok(String)`: A string that represents the number in the requested units with the requested precision.
-    * `Err(&'static str)`: An error if `num` is not a normal number or if `precision` is not in the range 0-3.
+    * `Err(Error)`: [Error::HumanNumber] if `num` is not a normal number, or
+      [Error::HumanPrecision] if `precision` is not in the range 0-3.
     */
-    pub fn to_human(num: f64, metric: bool, precision: usize) -> Result<String, &'static str> {
+    pub fn to_human(num: f64, metric: bool, precision: usize) -> Result<String, Error> {
         if !(num.is_normal() || num == 0.0) {
-            return Err("num must be a regular number");
+            return Err(Error::HumanNumber(num));
         }
         if precision > 3 {
-            return Err("precision must be in range 0-3");
+            return Err(Error::HumanPrecision(precision));
         }
 
         let unit_labels: [&str; 9] = if metric {
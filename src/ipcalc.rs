@@ -0,0 +1,699 @@
+// Copyright (c) 2026 Mikko Tanner. All rights reserved.
+// Licensed under the MIT License or the Apache License, Version 2.0.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! `ipcalc`: the [miniutils::iptools] toolbox for the command line.
+
+use miniutils::{
+    iptools::{
+        classify_ip, collapse_cidrs, collapse_cidrs_budget, collapse_cidrs_to_ranges,
+        collapse_ranges_fuzzy, collapse_specs, exclude_cidrs, parse_ip_or_range_zoned, strip_zones,
+        AddressError, AddressSpec, Cidr, IpRange, RangeFormat,
+    },
+    simple_tabulate,
+};
+use std::{
+    env, error, fmt,
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    net::IpAddr,
+    path::Path,
+    process::ExitCode,
+};
+
+static USAGE: &str = "\
+usage: ipcalc <command> [options] [args]
+
+commands:
+  collapse [FILE...]      collapse IPs, CIDRs and ranges into minimal CIDRs
+      --max-gap N         also merge blocks separated by <= N addresses
+      --budget N          emit at most N blocks (over-approximates)
+  expand [SPEC...]        list the addresses of IPs, CIDRs (hosts only) and ranges
+  info CIDR...            subnet information
+  contains SET ADDR...    check whether addresses/CIDRs are within SET
+                          (a file, '-', or a comma-separated list)
+  diff OLD NEW            blocks removed from (-) and added to (+) OLD in NEW
+  exclude FILE EXCLUDE    the addresses of FILE which are not in EXCLUDE
+  classify [IP...]        special-purpose classification of addresses
+
+  --format FORMAT         output format of collapse, diff and exclude:
+                          list     one CIDR per line (default)
+                          table    CIDRs with first/last address and size
+                          range    start-end ranges (10.0.0.1-10.0.0.9)
+                          short    short ranges where possible (10.0.0.1-9)
+                          shortest whichever of IP, CIDR or range is shortest
+  --strip-zones           collapse, diff and exclude: ignore IPv6 zones
+                          (fe80::1%eth0), which otherwise keep blocks apart
+
+Commands read standard input when FILE is '-' or no arguments are given.
+Blank lines and '#' comments in input are ignored.
+
+exit status: 0 ok, 1 negative result (not contained, sets differ),
+             2 usage error, 3 invalid input, 4 I/O error";
+
+static STDIN: &str = "<stdin>";
+static OPT_MAX_GAP: &str = "--max-gap";
+static OPT_BUDGET: &str = "--budget";
+static OPT_FORMAT: &str = "--format";
+static OPT_STRIP_ZONES: &str = "--strip-zones";
+
+const EXIT_OK: u8 = 0;
+/// negative answer: not contained, sets differ
+const EXIT_FALSE: u8 = 1;
+const EXIT_USAGE: u8 = 2;
+const EXIT_INPUT: u8 = 3;
+const EXIT_IO: u8 = 4;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut out: BufWriter<io::StdoutLock> = BufWriter::new(io::stdout().lock());
+    match run(&args, &mut out) {
+        Ok(code) => ExitCode::from(code),
+        Err(e) => {
+            eprintln!("ipcalc: {e}");
+            if let CliError::Usage(_) = e {
+                eprintln!("try 'ipcalc --help'");
+            }
+            ExitCode::from(e.code())
+        }
+    }
+}
+
+/// Run the command line in `args`, writing results to `out`. Returns the exit status.
+fn run(args: &[String], out: &mut dyn Write) -> Result<u8, CliError> {
+    let Some((cmd, rest)) = args.split_first() else {
+        return Err(CliError::Usage("missing command".into()));
+    };
+    match cmd.as_str() {
+        "-h" | "--help" | "help" => {
+            emit(out, [USAGE.to_string()])?;
+            Ok(EXIT_OK)
+        }
+        "collapse" => cmd_collapse(
+            &Opts::parse(
+                rest,
+                &[OPT_MAX_GAP, OPT_BUDGET, OPT_FORMAT, OPT_STRIP_ZONES],
+            )?,
+            out,
+        ),
+        "expand" => cmd_expand(&Opts::parse(rest, &[])?, out),
+        "info" => cmd_info(&Opts::parse(rest, &[])?, out),
+        "contains" => cmd_contains(&Opts::parse(rest, &[])?, out),
+        "diff" => cmd_diff(&Opts::parse(rest, &[OPT_FORMAT, OPT_STRIP_ZONES])?, out),
+        "exclude" => cmd_exclude(&Opts::parse(rest, &[OPT_FORMAT, OPT_STRIP_ZONES])?, out),
+        "classify" => cmd_classify(&Opts::parse(rest, &[])?, out),
+        other => Err(CliError::Usage(format!("unknown command '{other}'"))),
+    }
+}
+
+/* ---------------------------------- */
+
+fn cmd_collapse(opts: &Opts, out: &mut dyn Write) -> Result<u8, CliError> {
+    let mut cidrs: Vec<Cidr> = opts.zones(read_sets(&opts.args)?);
+    if let Some(gap) = opts.max_gap.filter(|g| *g > 0) {
+        let ranges: Vec<IpRange> = cidrs.iter().map(|c| IpRange::from(*c)).collect();
+        cidrs = collapse_ranges_fuzzy(&ranges, gap).expect("families are never mixed");
+    }
+    if let Some(budget) = opts.budget {
+        cidrs = collapse_cidrs_budget(&cidrs, budget);
+    }
+    emit(out, opts.format.render(&cidrs))?;
+    Ok(EXIT_OK)
+}
+
+fn cmd_expand(opts: &Opts, out: &mut dyn Write) -> Result<u8, CliError> {
+    let mut lines: Vec<String> = Vec::new();
+    for (at, spec) in entries(&opts.args)? {
+        let (ips, zone) = parse_ip_or_range_zoned(&spec).map_err(|e| at.error(chain(&e)))?;
+        lines.extend(ips.iter().map(|ip| match zone {
+            Some(z) => format!("{ip}%{z}"),
+            None => ip.to_string(),
+        }));
+    }
+    emit(out, lines)?;
+    Ok(EXIT_OK)
+}
+
+fn cmd_info(opts: &Opts, out: &mut dyn Write) -> Result<u8, CliError> {
+    if opts.args.is_empty() {
+        return Err(CliError::Usage("info needs at least one CIDR".into()));
+    }
+    let mut lines: Vec<String> = Vec::new();
+    for (at, spec) in arg_entries(&opts.args) {
+        let cidr: Cidr = spec
+            .parse()
+            .map_err(|e: AddressError| at.error(chain(&e)))?;
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+        lines.extend(cidr.info().to_table());
+    }
+    emit(out, lines)?;
+    Ok(EXIT_OK)
+}
+
+fn cmd_contains(opts: &Opts, out: &mut dyn Write) -> Result<u8, CliError> {
+    let Some((set, queries)) = opts.args.split_first() else {
+        return Err(CliError::Usage("contains needs a SET".into()));
+    };
+    if set == "-" && queries.is_empty() {
+        return Err(CliError::Usage(
+            "SET and addresses cannot both be stdin".into(),
+        ));
+    }
+    let set: Vec<Cidr> = read_set_or_literal(set, 1)?;
+    let queries: Vec<(Location, String)> = match queries.is_empty() {
+        true => read_lines("-")?,
+        false => arg_entries(&opts.args).into_iter().skip(1).collect(),
+    };
+
+    let mut code: u8 = EXIT_OK;
+    let mut rows: Vec<[String; 3]> = Vec::with_capacity(queries.len());
+    for (at, spec) in queries {
+        let query: Cidr = spec
+            .parse()
+            .map_err(|e: AddressError| at.error(chain(&e)))?;
+        let block: Option<&Cidr> = set.iter().find(|c| c.contains(&query));
+        if block.is_none() {
+            code = EXIT_FALSE;
+        }
+        rows.push([
+            spec,
+            if block.is_some() { "yes" } else { "no" }.to_string(),
+            block.map_or_else(|| "-".to_string(), |c| c.to_string()),
+        ]);
+    }
+    emit(
+        out,
+        simple_tabulate(rows, Some(&["address", "contained", "block"])),
+    )?;
+    Ok(code)
+}
+
+fn cmd_diff(opts: &Opts, out: &mut dyn Write) -> Result<u8, CliError> {
+    let [old, new] = opts.args.as_slice() else {
+        return Err(CliError::Usage("diff needs OLD and NEW".into()));
+    };
+    let (old, new): (Vec<Cidr>, Vec<Cidr>) =
+        (opts.zones(read_set(old)?), opts.zones(read_set(new)?));
+
+    let mut changes: Vec<(char, Cidr)> = exclude_cidrs(&old, &new)
+        .into_iter()
+        .map(|c| ('-', c))
+        .chain(exclude_cidrs(&new, &old).into_iter().map(|c| ('+', c)))
+        .collect();
+    changes.sort_by_key(|(_, c)| (c.addr, c.prefix));
+
+    let lines: Vec<String> = match opts.format {
+        Format::List => changes.iter().map(|(m, c)| format!("{m} {c}")).collect(),
+        Format::Table => simple_tabulate(
+            changes
+                .iter()
+                .map(|(m, c)| [m.to_string(), c.to_string(), c.len().to_string()]),
+            Some(&["", "block", "addresses"]),
+        ),
+        Format::Ranges(fmt) => {
+            // adjacent CIDRs of the same side become one range
+            let side = |mark: char| -> Vec<(char, IpRange)> {
+                let cidrs: Vec<Cidr> = changes
+                    .iter()
+                    .filter(|(m, _)| *m == mark)
+                    .map(|(_, c)| *c)
+                    .collect();
+                collapse_cidrs_to_ranges(&cidrs, 0)
+                    .into_iter()
+                    .map(|r| (mark, r))
+                    .collect()
+            };
+            let mut ranges: Vec<(char, IpRange)> = side('-');
+            ranges.extend(side('+'));
+            ranges.sort_by_key(|(_, r)| r.beg);
+            ranges
+                .iter()
+                .map(|(m, r)| format!("{m} {}", r.format(fmt)))
+                .collect()
+        }
+    };
+    emit(out, lines)?;
+    Ok(if changes.is_empty() {
+        EXIT_OK
+    } else {
+        EXIT_FALSE
+    })
+}
+
+fn cmd_exclude(opts: &Opts, out: &mut dyn Write) -> Result<u8, CliError> {
+    let [base, exclude] = opts.args.as_slice() else {
+        return Err(CliError::Usage("exclude needs FILE and EXCLUDE".into()));
+    };
+    let left: Vec<Cidr> = exclude_cidrs(
+        &opts.zones(read_set(base)?),
+        &opts.zones(read_set_or_literal(exclude, 2)?),
+    );
+    emit(out, opts.format.render(&left))?;
+    Ok(EXIT_OK)
+}
+
+fn cmd_classify(opts: &Opts, out: &mut dyn Write) -> Result<u8, CliError> {
+    let mut rows: Vec<[String; 2]> = Vec::new();
+    for (at, spec) in entries(&opts.args)? {
+        // the zone does not change what an address is
+        let ip: IpAddr = spec
+            .split('%')
+            .next()
+            .unwrap_or_default()
+            .parse()
+            .map_err(|_| at.error(format!("invalid IP address '{spec}'")))?;
+        rows.push([spec, classify_ip(ip).to_string()]);
+    }
+    emit(out, simple_tabulate(rows, Some(&["address", "kind"])))?;
+    Ok(EXIT_OK)
+}
+
+/* ---------------------------------- */
+
+/// Parsed command line of a single command.
+#[derive(Debug, Default)]
+struct Opts {
+    args: Vec<String>,
+    max_gap: Option<u128>,
+    budget: Option<usize>,
+    format: Format,
+    strip_zones: bool,
+}
+
+impl Opts {
+    /// Split `args` into options (only those in `allowed`) and positional arguments.
+    fn parse(args: &[String], allowed: &[&str]) -> Result<Self, CliError> {
+        let mut opts: Opts = Opts::default();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            if arg == "--" {
+                opts.args.extend(iter.cloned());
+                break;
+            }
+            if !arg.starts_with("--") {
+                opts.args.push(arg.clone());
+                continue;
+            }
+
+            let (name, inline): (&str, Option<&str>) = match arg.split_once('=') {
+                Some((n, v)) => (n, Some(v)),
+                None => (arg.as_str(), None),
+            };
+            if !allowed.contains(&name) {
+                return Err(CliError::Usage(format!("unknown option '{name}'")));
+            }
+            if name == OPT_STRIP_ZONES {
+                opts.strip_zones = true;
+                continue;
+            }
+            let value: &str = match inline {
+                Some(v) => v,
+                None => iter
+                    .next()
+                    .ok_or_else(|| CliError::Usage(format!("{name} needs a value")))?,
+            };
+            let bad = || CliError::Usage(format!("invalid value for {name}: '{value}'"));
+            match name {
+                n if n == OPT_MAX_GAP => opts.max_gap = Some(value.parse().map_err(|_| bad())?),
+                n if n == OPT_BUDGET => opts.budget = Some(value.parse().map_err(|_| bad())?),
+                n if n == OPT_FORMAT => {
+                    opts.format = match value {
+                        "list" => Format::List,
+                        "table" => Format::Table,
+                        "range" => Format::Ranges(RangeFormat::Full),
+                        "short" => Format::Ranges(RangeFormat::Short),
+                        "shortest" => Format::Ranges(RangeFormat::Shortest),
+                        _ => return Err(bad()),
+                    }
+                }
+                _ => unreachable!("option is in allowed"),
+            }
+        }
+        Ok(opts)
+    }
+
+    /// Apply `--strip-zones` to a collapsed set.
+    fn zones(&self, cidrs: Vec<Cidr>) -> Vec<Cidr> {
+        match self.strip_zones {
+            true => collapse_cidrs(&strip_zones(&cidrs), 0),
+            false => cidrs,
+        }
+    }
+}
+
+/// Output format of CIDR lists.
+#[derive(Clone, Copy, Debug, Default)]
+enum Format {
+    /// one CIDR per line
+    #[default]
+    List,
+    /// CIDR, first and last address, number of addresses
+    Table,
+    /// one range per line, adjacent CIDRs merged
+    Ranges(RangeFormat),
+}
+
+impl Format {
+    fn render(&self, cidrs: &[Cidr]) -> Vec<String> {
+        match self {
+            Format::List => cidrs.iter().map(|c| c.to_string()).collect(),
+            Format::Table => simple_tabulate(
+                cidrs.iter().map(|c| {
+                    let r: IpRange = IpRange::from(*c);
+                    [
+                        c.to_string(),
+                        r.beg.to_string(),
+                        r.end.to_string(),
+                        c.len().to_string(),
+                    ]
+                }),
+                Some(&["block", "first", "last", "addresses"]),
+            ),
+            Format::Ranges(fmt) => collapse_cidrs_to_ranges(cidrs, 0)
+                .iter()
+                .map(|r| r.format(*fmt))
+                .collect(),
+        }
+    }
+}
+
+/* ---------------------------------- */
+
+/// Where an input entry came from, for error messages.
+#[derive(Clone, Debug)]
+enum Location {
+    /// 1-based command line argument number (after the command)
+    Arg(usize),
+    Line {
+        src: String,
+        line: usize,
+    },
+}
+
+impl Location {
+    fn error(&self, msg: impl fmt::Display) -> CliError {
+        CliError::Input {
+            at: self.clone(),
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Location::Arg(n) => write!(f, "argument {n}"),
+            Location::Line { src, line } => write!(f, "{src}:{line}"),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum CliError {
+    Usage(String),
+    Input { at: Location, msg: String },
+    Io { path: String, source: io::Error },
+}
+
+impl CliError {
+    fn code(&self) -> u8 {
+        match self {
+            CliError::Usage(_) => EXIT_USAGE,
+            CliError::Input { .. } => EXIT_INPUT,
+            CliError::Io { .. } => EXIT_IO,
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Usage(msg) => f.write_str(msg),
+            CliError::Input { at, msg } => write!(f, "{at}: {msg}"),
+            CliError::Io { path, source } => write!(f, "{path}: {source}"),
+        }
+    }
+}
+
+/* ---------------------------------- */
+
+/// Command line arguments as entries, numbered for error messages.
+fn arg_entries(args: &[String]) -> Vec<(Location, String)> {
+    args.iter()
+        .enumerate()
+        .map(|(i, a)| (Location::Arg(i + 1), a.trim().to_string()))
+        .collect()
+}
+
+/// Entries from the arguments, or from standard input if there are none.
+fn entries(args: &[String]) -> Result<Vec<(Location, String)>, CliError> {
+    match args.is_empty() {
+        true => read_lines("-"),
+        false => Ok(arg_entries(args)),
+    }
+}
+
+/// Non-empty, comment-stripped lines of a file (`-` is standard input).
+fn read_lines(path: &str) -> Result<Vec<(Location, String)>, CliError> {
+    let (src, reader): (String, Box<dyn BufRead>) = match path {
+        "-" => (STDIN.to_string(), Box::new(io::stdin().lock())),
+        _ => {
+            let file: File = File::open(path).map_err(|source| CliError::Io {
+                path: path.to_string(),
+                source,
+            })?;
+            (path.to_string(), Box::new(BufReader::new(file)))
+        }
+    };
+
+    let mut out: Vec<(Location, String)> = Vec::new();
+    for (idx, line) in reader.lines().enumerate() {
+        let line: String = line.map_err(|source| CliError::Io {
+            path: src.clone(),
+            source,
+        })?;
+        let entry: &str = line.split('#').next().unwrap_or_default().trim();
+        if !entry.is_empty() {
+            let at: Location = Location::Line {
+                src: src.clone(),
+                line: idx + 1,
+            };
+            out.push((at, entry.to_string()));
+        }
+    }
+    Ok(out)
+}
+
+/// Parse entries (IPs, CIDRs or ranges) into the minimal CIDRs covering them.
+fn collapse_entries(entries: Vec<(Location, String)>) -> Result<Vec<Cidr>, CliError> {
+    let mut specs: Vec<AddressSpec> = Vec::with_capacity(entries.len());
+    for (at, spec) in entries {
+        specs.push(spec.parse().map_err(|e| at.error(chain(&e)))?);
+    }
+    Ok(collapse_specs(&specs, 0).expect("parsed specs never mix families"))
+}
+
+/// Read and collapse a single set file.
+fn read_set(path: &str) -> Result<Vec<Cidr>, CliError> {
+    collapse_entries(read_lines(path)?)
+}
+
+/// Read and collapse the union of set files, or standard input if there are none.
+fn read_sets(paths: &[String]) -> Result<Vec<Cidr>, CliError> {
+    let mut all: Vec<(Location, String)> = Vec::new();
+    for path in paths {
+        all.extend(read_lines(path)?);
+    }
+    if paths.is_empty() {
+        all = read_lines("-")?;
+    }
+    collapse_entries(all)
+}
+
+/**
+A set file, or if no such file exists, a comma-separated list of entries
+given as command line argument number `n`.
+*/
+fn read_set_or_literal(arg: &str, n: usize) -> Result<Vec<Cidr>, CliError> {
+    if arg == "-" || Path::new(arg).exists() {
+        return read_set(arg);
+    }
+    let entries: Vec<(Location, String)> = arg
+        .split(',')
+        .map(|s| (Location::Arg(n), s.trim().to_string()))
+        .filter(|(_, s)| !s.is_empty())
+        .collect();
+    collapse_entries(entries)
+}
+
+/// An error and its causes as one line, f.ex. `invalid prefix in CIDR: 'x': invalid digit`.
+fn chain(e: &dyn error::Error) -> String {
+    let mut msg: String = e.to_string();
+    let mut cause: Option<&dyn error::Error> = e.source();
+    while let Some(c) = cause {
+        msg = format!("{msg}: {c}");
+        cause = c.source();
+    }
+    msg
+}
+
+/// Write lines to `out`. A closed pipe (f.ex. `| head`) is not an error.
+fn emit(out: &mut dyn Write, lines: impl IntoIterator<Item = String>) -> Result<(), CliError> {
+    let res: io::Result<()> = lines
+        .into_iter()
+        .try_for_each(|l| writeln!(out, "{l}"))
+        .and_then(|_| out.flush());
+    match res {
+        Err(e) if e.kind() != io::ErrorKind::BrokenPipe => Err(CliError::Io {
+            path: "<stdout>".to_string(),
+            source: e,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/* -------------------------------------------------------------------------- */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs, path::PathBuf, process};
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    /// Run a command line, returning the exit status and the output lines.
+    fn call(cmdline: &str) -> Result<(u8, Vec<String>), CliError> {
+        let mut out: Vec<u8> = Vec::new();
+        let code: u8 = run(&args(cmdline), &mut out)?;
+        let text: String = String::from_utf8(out).unwrap();
+        Ok((code, text.lines().map(String::from).collect()))
+    }
+
+    /// Write a set file in the temp dir, returning its path.
+    fn set_file(name: &str, content: &str) -> String {
+        let path: PathBuf = env::temp_dir().join(format!("ipcalc-{name}-{}", process::id()));
+        fs::write(&path, content).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_opts_parse() {
+        let all: [&str; 4] = [OPT_MAX_GAP, OPT_BUDGET, OPT_FORMAT, OPT_STRIP_ZONES];
+        let opts: Opts = Opts::parse(
+            &args("a --max-gap=5 --budget 3 b --format shortest --strip-zones -- --c"),
+            &all,
+        )
+        .unwrap();
+        assert_eq!(opts.args, ["a", "b", "--c"]);
+        assert_eq!(opts.max_gap, Some(5));
+        assert_eq!(opts.budget, Some(3));
+        assert!(matches!(opts.format, Format::Ranges(RangeFormat::Shortest)));
+        assert!(opts.strip_zones);
+
+        for (cmdline, msg) in [
+            ("--budget", "--budget needs a value"),
+            ("--budget=lots", "invalid value for --budget: 'lots'"),
+            ("--max-gap -1", "invalid value for --max-gap: '-1'"),
+            ("--format csv", "invalid value for --format: 'csv'"),
+            ("--verbose", "unknown option '--verbose'"),
+        ] {
+            let err: CliError = Opts::parse(&args(cmdline), &all).unwrap_err();
+            assert!(matches!(err, CliError::Usage(_)), "{cmdline}");
+            assert_eq!(err.to_string(), msg);
+            assert_eq!(err.code(), EXIT_USAGE);
+        }
+        // options are only accepted by the commands which use them
+        let err: CliError = call("contains --format table 10.0.0.0/8").unwrap_err();
+        assert_eq!(err.to_string(), "unknown option '--format'");
+        assert_eq!(call("").unwrap_err().code(), EXIT_USAGE);
+        assert_eq!(call("frobnicate").unwrap_err().code(), EXIT_USAGE);
+        assert_eq!(call("--help").unwrap().0, EXIT_OK);
+    }
+
+    #[test]
+    fn test_contains_exit_codes() {
+        let (code, out) =
+            call("contains 10.0.0.0/8,192.168.0.0/16 10.1.2.3 192.168.1.0/24").unwrap();
+        assert_eq!(code, EXIT_OK);
+        assert_eq!(out[2], "10.1.2.3       | yes       | 10.0.0.0/8    ");
+        assert_eq!(out[3], "192.168.1.0/24 | yes       | 192.168.0.0/16");
+
+        let (code, out) = call("contains 10.0.0.0/8 10.1.2.3 172.16.0.1").unwrap();
+        assert_eq!(code, EXIT_FALSE);
+        assert_eq!(out[3], "172.16.0.1 | no        | -         ");
+
+        let err: CliError = call("contains 10.0.0.0/8 10.1.2.3 10.0.0.0/x").unwrap_err();
+        assert_eq!(err.code(), EXIT_INPUT);
+        // with the cause of the parse error
+        assert_eq!(
+            err.to_string(),
+            "argument 3: invalid prefix in CIDR: 'x': invalid digit found in string"
+        );
+        assert_eq!(call("contains").unwrap_err().code(), EXIT_USAGE);
+    }
+
+    #[test]
+    fn test_diff_exit_codes() {
+        let old: String = set_file("diff-old", "10.0.0.0/24\n# comment\n10.0.1.0/24\n");
+        let same: String = set_file("diff-same", "10.0.0.0/23\n");
+        let new: String = set_file("diff-new", "10.0.0.0/24\n10.0.2.0/24\n");
+
+        assert_eq!(
+            call(&format!("diff {old} {same}")).unwrap(),
+            (EXIT_OK, vec![])
+        );
+        let (code, out) = call(&format!("diff {old} {new}")).unwrap();
+        assert_eq!(code, EXIT_FALSE);
+        assert_eq!(out, ["- 10.0.1.0/24", "+ 10.0.2.0/24"]);
+        assert_eq!(call(&format!("diff {old}")).unwrap_err().code(), EXIT_USAGE);
+
+        for path in [old, same, new] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_input_errors() {
+        let path: String = set_file("bad", "10.0.0.0/24\n\n# comment\n10.0.1.0/33\n");
+        let err: CliError = call(&format!("collapse {path}")).unwrap_err();
+        assert_eq!(err.code(), EXIT_INPUT);
+        assert!(err.to_string().starts_with(&format!("{path}:4: ")), "{err}");
+        fs::remove_file(&path).unwrap();
+
+        let err: CliError = call(&format!("collapse {path}")).unwrap_err();
+        assert!(matches!(err, CliError::Io { .. }));
+        assert_eq!(err.code(), EXIT_IO);
+    }
+
+    #[test]
+    fn test_format_render() {
+        let cidrs: Vec<Cidr> = ["10.0.0.0/31", "10.0.0.2/32", "10.0.1.0/24"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        let render = |format: &str| -> Vec<String> {
+            let opts: Opts =
+                Opts::parse(&args(&format!("--format {format}")), &[OPT_FORMAT]).unwrap();
+            opts.format.render(&cidrs)
+        };
+
+        assert_eq!(
+            render("list"),
+            ["10.0.0.0/31", "10.0.0.2/32", "10.0.1.0/24"]
+        );
+        let table: Vec<String> = render("table");
+        assert_eq!(table.len(), 2 + cidrs.len());
+        assert!(table[0].starts_with("block") && table[0].ends_with("addresses"));
+        assert_eq!(table[4], "10.0.1.0/24 | 10.0.1.0 | 10.0.1.255 | 256      ");
+        assert_eq!(
+            render("range"),
+            ["10.0.0.0-10.0.0.2", "10.0.1.0-10.0.1.255"]
+        );
+        assert_eq!(render("short"), ["10.0.0.0-2", "10.0.1.0-255"]);
+        assert_eq!(render("shortest"), ["10.0.0.0-2", "10.0.1.0/24"]);
+    }
+}
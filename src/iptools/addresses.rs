@@ -2,9 +2,14 @@
 // Licensed under the MIT License or the Apache License, Version 2.0.
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use super::{strings::*, structs::IpRange, AddressError, IPV4_BITS, IPV6_BITS, MAX_RANGE_SIZE};
-use ipnet::IpNet;
-use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use super::{
+    prelude::*,
+    strings::*,
+    structs::{Cidr, IpRange},
+    zone::{check_zone, merge_zones, parse_zoned_ip, split_zone, ZoneId},
+    AddressError, MAX_RANGE_SIZE,
+};
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
 static IP_DELIMS: &[char] = &['.', ':'];
 
@@ -13,7 +18,7 @@ Parse an IP address, CIDR, or IP range from a string and return all individual I
 
 Supported formats:
 - Single IP: 10.10.10.1
-- CIDR: 10.10.10.0/28
+- CIDR: 10.10.10.0/28 (usable hosts only, see [Cidr::hosts])
 - Short range: 10.10.10.1-10 (last octet range)
 - Full range: 10.10.10.1-10.10.10.10
 
@@ -21,36 +26,43 @@ NOTE: refuses to generate ranges larger than [MAX_RANGE_SIZE] to guard
 against an obvious footgun scenario, especially with IPv6.
 */
 pub fn parse_ip_or_range(arg: impl AsRef<str>) -> Result<Vec<IpAddr>, AddressError> {
+    parse_ip_or_range_zoned(arg).map(|(ips, _)| ips)
+}
+
+/**
+Like [parse_ip_or_range], but also returns the IPv6 zone of the input, if
+any, f.ex. `fe80::1%eth0`, `fe80::/126%eth0` or `fe80::1-5%eth0`. All the
+returned addresses are in that zone.
+*/
+pub fn parse_ip_or_range_zoned(
+    arg: impl AsRef<str>,
+) -> Result<(Vec<IpAddr>, Option<ZoneId>), AddressError> {
+    let arg: &str = arg.as_ref();
+
     // Try single IP first
-    if let Ok(ip) = arg.as_ref().parse::<IpAddr>() {
-        return Ok(vec![ip]);
+    if let Ok((ip, zone)) = parse_zoned_ip(arg) {
+        return Ok((vec![ip], zone));
     }
 
     // Try CIDR notation
-    if let Ok(network) = arg.as_ref().parse::<IpNet>() {
-        let bits = match network {
-            IpNet::V4(_) => IPV4_BITS,
-            IpNet::V6(_) => IPV6_BITS,
-        };
-        let num_addrs: u128 = 1u128 << (bits - network.prefix_len());
+    if arg.contains(SLASH) {
+        let cidr: Cidr = arg
+            .parse()
+            .map_err(|_| AddressError::Invalid(arg.to_string()))?;
+        let num_addrs: u128 = cidr.len();
         if num_addrs > MAX_RANGE_SIZE as u128 {
             return Err(AddressError::RangeTooLarge(num_addrs));
         }
-        let hosts: Vec<IpAddr> = network.hosts().collect();
-        if hosts.is_empty() {
-            // For /32 or /128, use the network address itself
-            return Ok(vec![network.addr()]);
-        }
-        return Ok(hosts);
+        return Ok((cidr.hosts().collect(), cidr.zone));
     }
 
     // Try range notation (10.10.10.1-10 or 10.10.10.1-10.10.10.10)
-    if arg.as_ref().contains(DASH) {
-        let range: IpRange = parse_ip_range(arg.as_ref())?;
-        return generate_ip_range(range.beg, range.end);
+    if arg.contains(DASH) {
+        let range: IpRange = parse_ip_range(arg)?;
+        return Ok((generate_ip_range(range.beg, range.end)?, range.zone));
     }
 
-    Err(AddressError::Invalid(arg.as_ref().to_string()))
+    Err(AddressError::Invalid(arg.to_string()))
 }
 
 /**
@@ -58,17 +70,41 @@ Parse an IP range in the format:
 - 10.10.10.1-10 (short form, last octet only)
 - 10.10.10.1-10.10.10.10 (full form)
 
+An IPv6 zone may be given on either or both endpoints (`fe80::1%eth0-5`,
+`fe80::1-fe80::5%eth0`), but both must then be in the same zone.
+
 ### Returns
 - [IpRange] struct with start and end IP addresses (inclusive).
 */
 pub fn parse_ip_range(arg: impl AsRef<str>) -> Result<IpRange, AddressError> {
-    let parts: Vec<&str> = arg.as_ref().split(DASH).collect();
-    if parts.len() != 2 {
-        return Err(AddressError::InvalidRangeFmt(arg.as_ref().into()));
+    parse_ip_range_notation(arg.as_ref()).map(|(range, _)| range)
+}
+
+/// Like [parse_ip_range], but also returns whether the end was in short form.
+pub(crate) fn parse_ip_range_notation(arg: &str) -> Result<(IpRange, bool), AddressError> {
+    let dashes: Vec<usize> = arg.match_indices(DASH).map(|(i, _)| i).collect();
+    match dashes.as_slice() {
+        [] => Err(AddressError::InvalidRangeFmt(arg.into())),
+        [i] => parse_range_parts(arg, &arg[..*i], &arg[i + 1..]),
+        // zone IDs may contain dashes as well: take the first split which parses
+        _ => dashes
+            .iter()
+            .find_map(|i| parse_range_parts(arg, &arg[..*i], &arg[i + 1..]).ok())
+            .ok_or_else(|| AddressError::InvalidRangeFmt(arg.into())),
     }
+}
 
-    let beg_str: &str = parts[0].trim();
-    let end_str: &str = parts[1].trim();
+/**
+Parse the start and end of a range, and whether the end is in short form.
+`arg` is the whole range for error messages.
+*/
+fn parse_range_parts(
+    arg: &str,
+    beg_str: &str,
+    end_str: &str,
+) -> Result<(IpRange, bool), AddressError> {
+    let (beg_str, beg_zone) = split_zone(beg_str.trim())?;
+    let (end_str, end_zone) = split_zone(end_str.trim())?;
 
     // Parse the start IP
     let beg_ip = beg_str
@@ -79,7 +115,8 @@ pub fn parse_ip_range(arg: impl AsRef<str>) -> Result<IpRange, AddressError> {
         })?;
 
     // Determine if this is short form (just a number) or full IP
-    let end_ip = if end_str.contains(IP_DELIMS[0]) || end_str.contains(IP_DELIMS[1]) {
+    let short: bool = !end_str.contains(IP_DELIMS);
+    let end_ip = if !short {
         // Full IP form
         end_str
             .parse::<IpAddr>()
@@ -92,7 +129,9 @@ pub fn parse_ip_range(arg: impl AsRef<str>) -> Result<IpRange, AddressError> {
         parse_short_range_end(&beg_ip, end_str)?
     };
 
-    Ok(IpRange::new(beg_ip, end_ip)?)
+    let zone: Option<ZoneId> = merge_zones(beg_zone, end_zone)?;
+    check_zone(beg_ip, zone, arg)?;
+    Ok((IpRange::new(beg_ip, end_ip)?.with_zone(zone), short))
 }
 
 /// Parse short-form range end (e.g., "10" in "192.168.1.1-10")
@@ -193,10 +232,17 @@ mod tests {
     const TEST_V6_2: &str = "::5";
     const TEST_V6_3: &str = "::ffff";
     const RANGE_V6: &str = "::1-5";
+    const CIDR_V6: &str = "2001:db8::/126";
     const BAD_RANGE_V6: &str = "::5-1";
     const BIG_RANGE_V6: &str = "::1-::ffff";
     const TOOBIG_V6: &str = "::1-::ffff:ffff"; // 4B addresses
 
+    const ZONED_IP: &str = "fe80::1%eth0";
+    const ZONED_CIDR: &str = "fe80::/126%2";
+    const ZONED_RANGE: &str = "fe80::1%br-lan-fe80::3%br-lan";
+    const ZONE_MISMATCH: &str = "fe80::1%eth0-3%eth1";
+    const ZONED_V4: &str = "10.0.0.1%eth0";
+
     #[test]
     fn test_parse_single_ip() {
         let result: Vec<IpAddr> = parse_ip_or_range(TEST_1).unwrap();
@@ -212,6 +258,15 @@ mod tests {
         assert!(result.contains(&TEST_2.parse::<IpAddr>().unwrap()));
     }
 
+    #[test]
+    fn test_parse_cidr_matches_hosts() {
+        for s in [CIDR_1, CIDR_V6, "10.0.0.0/31", "10.0.0.1/32"] {
+            let cidr: Cidr = s.parse().unwrap();
+            let result: Vec<IpAddr> = parse_ip_or_range(s).unwrap();
+            assert_eq!(result, cidr.hosts().collect::<Vec<IpAddr>>(), "{s}");
+        }
+    }
+
     #[test]
     fn test_parse_short_range() {
         let result: Vec<IpAddr> = parse_ip_or_range(RANGE_1).unwrap();
@@ -268,4 +323,26 @@ mod tests {
         let result: Result<Vec<IpAddr>, AddressError> = parse_ip_or_range(TOOBIG_V6);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_zones() {
+        let zone = |s: &str| Some(s.parse::<ZoneId>().unwrap());
+
+        let (ips, z) = parse_ip_or_range_zoned(ZONED_IP).unwrap();
+        assert_eq!(
+            (ips, z),
+            (vec!["fe80::1".parse::<IpAddr>().unwrap()], zone("eth0"))
+        );
+        let (ips, z) = parse_ip_or_range_zoned(ZONED_CIDR).unwrap();
+        assert_eq!((ips.len(), z), (3, zone("2")));
+        let (ips, z) = parse_ip_or_range_zoned(ZONED_RANGE).unwrap();
+        assert_eq!((ips.len(), z), (3, zone("br-lan")));
+        assert_eq!(parse_ip_or_range(ZONED_RANGE).unwrap().len(), 3);
+
+        assert!(matches!(
+            parse_ip_range(ZONE_MISMATCH),
+            Err(AddressError::ZoneMismatch(_, _))
+        ));
+        assert!(parse_ip_or_range(ZONED_V4).is_err());
+    }
 }
@@ -0,0 +1,393 @@
+// Copyright (c) 2026 Mikko Tanner. All rights reserved.
+// Licensed under the MIT License or the Apache License, Version 2.0.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! ASCII maps of the allocations inside a supernet.
+
+use super::{
+    collapsing::{cidr_to_range, int_to_ip},
+    strings::*,
+    structs::{Cidr, Range},
+    tagged::label_segments,
+    IPV4_BITS, IPV6_BITS,
+};
+use crate::simple_tabulate;
+use std::{collections::BTreeSet, error, fmt, net::IpAddr};
+
+/// Most cells [AddressMap] will draw.
+pub const MAP_MAX_CELLS: u128 = 1 << 16;
+
+static LABEL_GLYPHS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+static SGR_RESET: &str = "\x1b[0m";
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MapError {
+    /**
+    cells must be at least as long as the parent prefix, and valid for its
+    family (IPv6 cells cannot be the whole address space)
+    */
+    InvalidCellPrefix(u8),
+    /// the map would have more than [MAP_MAX_CELLS] cells
+    TooManyCells(u128),
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapError::InvalidCellPrefix(p) => write!(f, "{ERR_MAP_PREFIX}: /{p}"),
+            MapError::TooManyCells(n) => write!(f, "{ERR_MAP_CELLS}: {n} > {MAP_MAX_CELLS}"),
+        }
+    }
+}
+
+impl error::Error for MapError {}
+
+/// Allocation state of one [MapCell].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub enum CellState {
+    /// no child covers any of the cell
+    Free,
+    /// children cover all of the cell, without overlapping
+    Used,
+    /// children cover some of the cell, without overlapping
+    Partial,
+    /// at least two children share addresses in the cell
+    Overlap,
+}
+
+impl CellState {
+    /// SGR color parameters: grey, green, yellow and bold red.
+    fn sgr(&self) -> &'static str {
+        match self {
+            CellState::Free => "90",
+            CellState::Used => "32",
+            CellState::Partial => "33",
+            CellState::Overlap => "1;31",
+        }
+    }
+}
+
+/// One cell of an [AddressMap].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MapCell {
+    pub cidr: Cidr,
+    pub state: CellState,
+    /// labels of the children in the cell, sorted
+    pub labels: Vec<String>,
+}
+
+/**
+Renders the children of a parent network as a grid of cells, f.ex. one cell
+per /24 of a /16, 16 cells per row. Each cell shows its [CellState]:
+
+- `.` free
+- `A`, `B`, ... used by the child with that label, see the legend
+- `a`, `b`, ... partially used by that label
+- `#` / `+` used / partially used by several labels (or past `Z`)
+- `!` overlapping children
+
+Children outside the parent (or in another zone) are clipped or ignored.
+With colors, cells are wrapped in ANSI SGR codes which
+[simple_tabulate](crate::simple_tabulate) does not count towards the width.
+*/
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AddressMap {
+    parent: Cidr,
+    cell_prefix: u8,
+    columns: usize,
+    color: bool,
+}
+
+impl AddressMap {
+    /// A map of `parent` with cells 8 bits longer than it (but at most host routes), 16 per row.
+    pub fn new(parent: Cidr) -> Self {
+        let parent: Cidr = parent.network();
+        Self {
+            parent,
+            cell_prefix: parent.prefix.saturating_add(8).min(bits(&parent)),
+            columns: 16,
+            color: false,
+        }
+    }
+
+    /// Cell size as a prefix length, f.ex. `24` for one cell per /24.
+    pub fn with_cell_prefix(mut self, prefix: u8) -> Self {
+        self.cell_prefix = prefix;
+        self
+    }
+
+    /// Cells per row. At least one; `usize::MAX` draws a single bar.
+    pub fn with_columns(mut self, columns: usize) -> Self {
+        self.columns = columns.max(1);
+        self
+    }
+
+    /// Color the cells with ANSI escape codes.
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Classify each cell of the map, in address order.
+    pub fn cells<L: fmt::Display>(&self, children: &[(Cidr, L)]) -> Result<Vec<MapCell>, MapError> {
+        let host_bits: u8 = self.check()?;
+        let parent: Range = cidr_to_range(self.parent);
+        let cell_len: u128 = 1u128 << host_bits;
+
+        let mut labels: Vec<String> = Vec::new();
+        let mut ranges: Vec<(Range, usize)> = Vec::new();
+        for (i, (cidr, label)) in children.iter().enumerate() {
+            let r: Range = cidr_to_range(*cidr);
+            if let Some(clipped) = r.intersection(&parent) {
+                ranges.push((clipped, i));
+            }
+            labels.push(label.to_string());
+        }
+
+        // covered addresses, labels and overlap per cell
+        let n: usize = self.num_cells() as usize;
+        let mut acc: Vec<(u128, BTreeSet<usize>, bool)> = vec![(0, BTreeSet::new(), false); n];
+        for (seg, ids) in label_segments(ranges) {
+            let first: usize = ((seg.beg - parent.beg) >> host_bits) as usize;
+            let last: usize = ((seg.end - parent.beg) >> host_bits) as usize;
+            for (c, cell) in acc.iter_mut().enumerate().take(last + 1).skip(first) {
+                let cell_beg: u128 = parent.beg + c as u128 * cell_len;
+                let cell_end: u128 = cell_beg + (cell_len - 1);
+                cell.0 += seg.end.min(cell_end) - seg.beg.max(cell_beg) + 1;
+                cell.1.extend(ids.iter().copied());
+                cell.2 |= ids.len() > 1;
+            }
+        }
+
+        Ok(acc
+            .into_iter()
+            .enumerate()
+            .map(|(c, (covered, ids, overlap))| MapCell {
+                cidr: Cidr {
+                    addr: int_to_ip(parent.fam, parent.beg + c as u128 * cell_len),
+                    prefix: self.cell_prefix,
+                    zone: self.parent.zone,
+                },
+                state: match (covered, overlap) {
+                    (_, true) => CellState::Overlap,
+                    (0, _) => CellState::Free,
+                    (c, _) if c == cell_len => CellState::Used,
+                    _ => CellState::Partial,
+                },
+                labels: ids
+                    .into_iter()
+                    .map(|id| labels[id].clone())
+                    .collect::<BTreeSet<String>>()
+                    .into_iter()
+                    .collect(),
+            })
+            .collect())
+    }
+
+    /**
+    Draw the map: a title line, one row of cells per line prefixed by the
+    row's first address, and a legend of the glyphs used.
+    */
+    pub fn render<L: fmt::Display>(&self, children: &[(Cidr, L)]) -> Result<Vec<String>, MapError> {
+        let cells: Vec<MapCell> = self.cells(children)?;
+
+        // glyphs in order of first appearance in the input
+        let mut order: Vec<String> = Vec::new();
+        for (_, label) in children {
+            let label: String = label.to_string();
+            if !order.contains(&label) {
+                order.push(label);
+            }
+        }
+        let glyph_of = |label: &str| -> Option<char> {
+            let i: usize = order.iter().position(|l| l == label)?;
+            LABEL_GLYPHS.get(i).map(|g| *g as char)
+        };
+
+        let mut used: BTreeSet<(char, CellState)> = BTreeSet::new();
+        let glyphs: Vec<String> = cells
+            .iter()
+            .map(|cell| {
+                let single: Option<char> = match cell.labels.as_slice() {
+                    [one] => glyph_of(one),
+                    _ => None,
+                };
+                let glyph: char = match (cell.state, single) {
+                    (CellState::Free, _) => '.',
+                    (CellState::Overlap, _) => '!',
+                    (CellState::Used, Some(g)) => g,
+                    (CellState::Used, None) => '#',
+                    (CellState::Partial, Some(g)) => g.to_ascii_lowercase(),
+                    (CellState::Partial, None) => '+',
+                };
+                used.insert((glyph, cell.state));
+                self.paint(glyph, cell.state)
+            })
+            .collect();
+
+        let title: String = format!(
+            "{}: {} cells of /{} ({} addresses each)",
+            self.parent,
+            cells.len(),
+            self.cell_prefix,
+            1u128 << (bits(&self.parent) - self.cell_prefix)
+        );
+        let rows = glyphs
+            .chunks(self.columns)
+            .enumerate()
+            .map(|(r, row)| [cells[r * self.columns].cidr.addr.to_string(), row.concat()]);
+
+        let legend = used.iter().map(|(glyph, state)| {
+            let meaning: String = match (glyph, state) {
+                ('.', _) => "free".into(),
+                ('!', _) => "overlapping children".into(),
+                ('#', _) => "used, several or other labels".into(),
+                ('+', _) => "partially used, several or other labels".into(),
+                (g, CellState::Partial) => {
+                    format!(
+                        "partially used: {}",
+                        order[label_index(g.to_ascii_uppercase())]
+                    )
+                }
+                (g, _) => format!("used: {}", order[label_index(*g)]),
+            };
+            [self.paint(*glyph, *state), meaning]
+        });
+
+        let mut out: Vec<String> = vec![title];
+        out.extend(simple_tabulate(rows, None));
+        out.push(String::new());
+        out.extend(simple_tabulate(legend, Some(&["cell", "meaning"])));
+        Ok(out)
+    }
+
+    /// Validate the cell size, returning the number of host bits per cell.
+    fn check(&self) -> Result<u8, MapError> {
+        let bits: u8 = bits(&self.parent);
+        if self.cell_prefix < self.parent.prefix
+            || self.cell_prefix > bits
+            || bits - self.cell_prefix >= u128::BITS as u8
+        {
+            return Err(MapError::InvalidCellPrefix(self.cell_prefix));
+        }
+        let cells: u128 = self.num_cells();
+        if cells > MAP_MAX_CELLS {
+            return Err(MapError::TooManyCells(cells));
+        }
+        Ok(bits - self.cell_prefix)
+    }
+
+    /// Saturating.
+    fn num_cells(&self) -> u128 {
+        1u128
+            .checked_shl((self.cell_prefix - self.parent.prefix) as u32)
+            .unwrap_or(u128::MAX)
+    }
+
+    fn paint(&self, glyph: char, state: CellState) -> String {
+        match self.color {
+            true => format!("\x1b[{}m{glyph}{SGR_RESET}", state.sgr()),
+            false => glyph.to_string(),
+        }
+    }
+}
+
+#[inline]
+fn bits(cidr: &Cidr) -> u8 {
+    match cidr.addr {
+        IpAddr::V4(_) => IPV4_BITS,
+        IpAddr::V6(_) => IPV6_BITS,
+    }
+}
+
+#[inline]
+fn label_index(glyph: char) -> usize {
+    (glyph as u8 - LABEL_GLYPHS[0]) as usize
+}
+
+/* -------------------------------------------------------------------------- */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::iptools::collapse_cidrs;
+    use regex::Regex;
+
+    fn children() -> Vec<(Cidr, &'static str)> {
+        [
+            ("10.0.0.0/23", "ops"),
+            ("10.0.2.0/25", "web"),
+            ("10.0.4.0/24", "web"),
+            ("10.0.4.128/25", "db"),
+            ("10.0.5.0/24", "ops"),
+            ("10.0.6.0/24", "db"),
+            ("10.1.0.0/16", "elsewhere"),
+        ]
+        .into_iter()
+        .map(|(c, l)| (c.parse().unwrap(), l))
+        .collect()
+    }
+
+    #[test]
+    fn test_cells() {
+        let map: AddressMap = AddressMap::new("10.0.0.0/16".parse().unwrap());
+        let cells: Vec<MapCell> = map.cells(&children()).unwrap();
+        assert_eq!(cells.len(), 256);
+        let states: Vec<CellState> = cells.iter().take(8).map(|c| c.state).collect();
+        use CellState::*;
+        assert_eq!(
+            states,
+            [Used, Used, Partial, Free, Overlap, Used, Used, Free]
+        );
+        assert_eq!(cells[4].labels, ["db", "web"]);
+        assert_eq!(cells[255].cidr.to_string(), "10.0.255.0/24");
+        assert!(cells[8..].iter().all(|c| c.state == Free));
+
+        // a fully used parent, at host granularity
+        let all: Vec<Cidr> = collapse_cidrs(&["10.0.0.0/30".parse().unwrap()], 0);
+        let host: AddressMap = AddressMap::new("10.0.0.0/30".parse().unwrap());
+        assert!(host
+            .cells(&[(all[0], "x")])
+            .unwrap()
+            .iter()
+            .all(|c| c.state == Used && c.cidr.is_host()));
+
+        let v6: AddressMap = AddressMap::new("2001:db8::/32".parse().unwrap());
+        assert_eq!(v6.cells::<&str>(&[]).unwrap().len(), 256);
+        assert_eq!(
+            v6.with_cell_prefix(56).cells::<&str>(&[]),
+            Err(MapError::TooManyCells(1 << 24))
+        );
+        assert_eq!(
+            v6.with_cell_prefix(16).cells::<&str>(&[]),
+            Err(MapError::InvalidCellPrefix(16))
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let map: AddressMap = AddressMap::new("10.0.0.0/20".parse().unwrap())
+            .with_cell_prefix(24)
+            .with_columns(8);
+        let out: Vec<String> = map.render(&children()).unwrap();
+        assert_eq!(out[0], "10.0.0.0/20: 16 cells of /24 (256 addresses each)");
+        assert_eq!(out[1], "10.0.0.0 | AAb.!AC.");
+        assert_eq!(out[2], "10.0.8.0 | ........");
+        let legend: Vec<&str> = out[4..].iter().map(|l| l.trim_end()).collect();
+        assert_eq!(legend[0], "cell | meaning");
+        assert!(legend.contains(&"b    | partially used: web"));
+        assert!(legend.contains(&"!    | overlapping children"));
+
+        // colors do not shift the columns
+        let color: Vec<String> = map.with_color(true).render(&children()).unwrap();
+        let ansi: Regex = Regex::new(r"\x1b\[[0-9;]*m").unwrap();
+        let stripped: Vec<String> = color
+            .iter()
+            .map(|l| ansi.replace_all(l, "").into_owned())
+            .collect();
+        assert_eq!(stripped, out);
+        assert!(color[1].starts_with("10.0.0.0 | \x1b[32mA\x1b[0m"));
+        let bar: Vec<String> = map.with_columns(usize::MAX).render(&children()).unwrap();
+        assert_eq!(bar[1], "10.0.0.0 | AAb.!AC.........");
+    }
+}
@@ -0,0 +1,274 @@
+// Copyright (c) 2026 Mikko Tanner. All rights reserved.
+// Licensed under the MIT License or the Apache License, Version 2.0.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! IP address anonymization for logs: truncation and prefix-preserving keys.
+
+use super::{
+    collapsing::{cidr_to_range, int_to_ip, ip_to_host_cidr},
+    structs::{Cidr, IpFam, Range},
+    IPV4_BITS, IPV6_BITS,
+};
+use core::{fmt, net::IpAddr};
+
+/// Default truncation prefixes: a typical customer network per family.
+const DEFAULT_V4_PREFIX: u8 = 24;
+const DEFAULT_V6_PREFIX: u8 = 48;
+
+/**
+Truncation-style anonymization: an address is replaced by the network of
+the configured prefix length, f.ex. `192.0.2.77` -> `192.0.2.0/24`.
+
+Cheap and not reversible, but all hosts of a network share a key.
+*/
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Truncation {
+    v4_prefix: u8,
+    v6_prefix: u8,
+}
+
+impl Default for Truncation {
+    fn default() -> Self {
+        Self {
+            v4_prefix: DEFAULT_V4_PREFIX,
+            v6_prefix: DEFAULT_V6_PREFIX,
+        }
+    }
+}
+
+impl Truncation {
+    /// Truncate IPv4 to /24 and IPv6 to /48.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Prefix length kept for IPv4 addresses, at most 32.
+    pub fn with_v4_prefix(mut self, prefix: u8) -> Self {
+        self.v4_prefix = prefix.min(IPV4_BITS);
+        self
+    }
+
+    /// Prefix length kept for IPv6 addresses, at most 128.
+    pub fn with_v6_prefix(mut self, prefix: u8) -> Self {
+        self.v6_prefix = prefix.min(IPV6_BITS);
+        self
+    }
+
+    /// The truncated network `ip` belongs to.
+    pub fn key(&self, ip: IpAddr) -> Cidr {
+        let prefix: u8 = match ip {
+            IpAddr::V4(_) => self.v4_prefix,
+            IpAddr::V6(_) => self.v6_prefix,
+        };
+        Cidr {
+            addr: ip,
+            prefix,
+            zone: None,
+        }
+        .network()
+    }
+}
+
+/* ---------------------------------- */
+
+/**
+Keyed, prefix-preserving anonymization in the Crypto-PAn family: two
+addresses sharing exactly a k-bit prefix map to two addresses which again
+share exactly a k-bit prefix, so subnet structure survives anonymization.
+
+Bit `i` of the output is bit `i` of the input flipped by a pseudo-random
+function of the key and the first `i` input bits. The PRF is SipHash-2-4
+rather than AES, which keeps this dependency-free; it is a keyed PRF, but
+*not* a cipher, so anyone holding the key can de-anonymize by brute force
+one bit at a time. Keep the key secret, and rotate it when joins across
+the old and new period are no longer needed.
+
+The mapping is a fixed bijection per key and family: the same key always
+gives the same output, and distinct addresses stay distinct.
+*/
+#[derive(Clone)]
+pub struct PrefixAnonymizer {
+    k0: u64,
+    k1: u64,
+}
+
+impl PrefixAnonymizer {
+    pub fn new(key: [u8; 16]) -> Self {
+        let (lo, hi) = key.split_at(8);
+        Self {
+            k0: u64::from_le_bytes(lo.try_into().expect("8 bytes")),
+            k1: u64::from_le_bytes(hi.try_into().expect("8 bytes")),
+        }
+    }
+
+    /// Anonymize a single address. Both families are supported.
+    pub fn anonymize(&self, ip: IpAddr) -> IpAddr {
+        let host: Range = cidr_to_range(ip_to_host_cidr(ip));
+        let bits: u32 = match host.fam {
+            IpFam::V4 => IPV4_BITS as u32,
+            IpFam::V6 => IPV6_BITS as u32,
+        };
+
+        // PRF input: family width, bit index, and the input bits before it
+        let mut msg: [u8; 18] = [0; 18];
+        msg[0] = bits as u8;
+        let mut out: u128 = 0;
+        for i in 0..bits {
+            let shift: u32 = bits - 1 - i;
+            let prefix: u128 = host.beg.checked_shr(shift + 1).unwrap_or(0);
+            msg[1] = i as u8;
+            msg[2..].copy_from_slice(&prefix.to_be_bytes());
+            let flip: u128 = (siphash24(self.k0, self.k1, &msg) & 1) as u128;
+            out |= (((host.beg >> shift) & 1) ^ flip) << shift;
+        }
+        int_to_ip(host.fam, out)
+    }
+
+    /**
+    Anonymize a network: the prefix is anonymized exactly as it would be in
+    any of its addresses, host bits are cleared. Prefix length and zone are
+    kept.
+    */
+    pub fn anonymize_cidr(&self, cidr: Cidr) -> Cidr {
+        Cidr {
+            addr: self.anonymize(cidr.network().addr),
+            ..cidr
+        }
+        .network()
+    }
+}
+
+impl fmt::Debug for PrefixAnonymizer {
+    /// Never print the key.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PrefixAnonymizer").finish_non_exhaustive()
+    }
+}
+
+/* ---------------------------------- */
+
+/**
+SipHash-2-4 (Aumasson & Bernstein) of `msg` with the key `k0 || k1`.
+
+`std`'s hashers make no stability promise across releases, which would
+break joining anonymized data produced by different builds.
+*/
+fn siphash24(k0: u64, k1: u64, msg: &[u8]) -> u64 {
+    let mut v: [u64; 4] = [
+        k0 ^ 0x736f_6d65_7073_6575,
+        k1 ^ 0x646f_7261_6e64_6f6d,
+        k0 ^ 0x6c79_6765_6e65_7261,
+        k1 ^ 0x7465_6462_7974_6573,
+    ];
+    let compress = |v: &mut [u64; 4], m: u64, rounds: usize| {
+        v[3] ^= m;
+        (0..rounds).for_each(|_| sip_round(v));
+        v[0] ^= m;
+    };
+
+    let chunks = msg.chunks_exact(8);
+    let tail: &[u8] = chunks.remainder();
+    for chunk in chunks {
+        compress(
+            &mut v,
+            u64::from_le_bytes(chunk.try_into().expect("8 bytes")),
+            2,
+        );
+    }
+    let mut last: u64 = (msg.len() as u64 & 0xff) << 56;
+    for (i, b) in tail.iter().enumerate() {
+        last |= (*b as u64) << (8 * i);
+    }
+    compress(&mut v, last, 2);
+
+    v[2] ^= 0xff;
+    (0..4).for_each(|_| sip_round(&mut v));
+    v[0] ^ v[1] ^ v[2] ^ v[3]
+}
+
+#[inline]
+fn sip_round(v: &mut [u64; 4]) {
+    v[0] = v[0].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(13) ^ v[0];
+    v[0] = v[0].rotate_left(32);
+    v[2] = v[2].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(16) ^ v[2];
+    v[0] = v[0].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(21) ^ v[0];
+    v[2] = v[2].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(17) ^ v[2];
+    v[2] = v[2].rotate_left(32);
+}
+
+/* -------------------------------------------------------------------------- */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: [u8; 16] = *b"0123456789abcdef";
+    const PAIRS: [(&str, &str); 4] = [
+        ("192.0.2.1", "192.0.2.200"),
+        ("10.1.2.3", "10.200.0.1"),
+        ("8.8.8.8", "200.1.1.1"),
+        ("2001:db8:1::1", "2001:db8:1:ff::1"),
+    ];
+
+    fn common_prefix(a: IpAddr, b: IpAddr) -> u32 {
+        let (a, b) = (
+            cidr_to_range(ip_to_host_cidr(a)),
+            cidr_to_range(ip_to_host_cidr(b)),
+        );
+        let bits: u32 = if a.fam == IpFam::V4 { 32 } else { 128 };
+        ((a.beg ^ b.beg).leading_zeros() - (128 - bits)).min(bits)
+    }
+
+    #[test]
+    fn test_siphash_reference_vector() {
+        // from the SipHash paper, appendix A
+        let key: [u8; 16] = core::array::from_fn(|i| i as u8);
+        let msg: [u8; 15] = core::array::from_fn(|i| i as u8);
+        let anon: PrefixAnonymizer = PrefixAnonymizer::new(key);
+        assert_eq!(siphash24(anon.k0, anon.k1, &msg), 0xa129_ca61_49be_45e5);
+    }
+
+    #[test]
+    fn test_truncation() {
+        let t: Truncation = Truncation::new();
+        assert_eq!(
+            t.key("192.0.2.77".parse().unwrap()).to_string(),
+            "192.0.2.0/24"
+        );
+        assert_eq!(
+            t.key("2001:db8:1:2::3".parse().unwrap()).to_string(),
+            "2001:db8:1::/48"
+        );
+        let t: Truncation = t.with_v4_prefix(16).with_v6_prefix(200);
+        assert_eq!(
+            t.key("192.0.2.77".parse().unwrap()).to_string(),
+            "192.0.0.0/16"
+        );
+        assert!(t.key("2001:db8::1".parse().unwrap()).is_host());
+    }
+
+    #[test]
+    fn test_prefix_preserving() {
+        let anon: PrefixAnonymizer = PrefixAnonymizer::new(KEY);
+        for (a, b) in PAIRS {
+            let (a, b): (IpAddr, IpAddr) = (a.parse().unwrap(), b.parse().unwrap());
+            let (x, y): (IpAddr, IpAddr) = (anon.anonymize(a), anon.anonymize(b));
+            assert_eq!(common_prefix(a, b), common_prefix(x, y), "{a} {b}");
+            assert_eq!(x.is_ipv4(), a.is_ipv4());
+            assert_ne!(x, a);
+            // stable for the same key, different for another
+            assert_eq!(PrefixAnonymizer::new(KEY).anonymize(a), x);
+            assert_ne!(PrefixAnonymizer::new([0; 16]).anonymize(a), x);
+        }
+
+        let net: Cidr = "192.0.2.0/24".parse().unwrap();
+        let anet: Cidr = anon.anonymize_cidr(net);
+        assert_eq!(anet.prefix, 24);
+        assert!(anet.contains_ip(anon.anonymize("192.0.2.1".parse().unwrap())));
+        assert_eq!(format!("{anon:?}"), "PrefixAnonymizer { .. }");
+    }
+}
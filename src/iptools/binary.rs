@@ -0,0 +1,606 @@
+// Copyright (c) 2026 Mikko Tanner. All rights reserved.
+// Licensed under the MIT License or the Apache License, Version 2.0.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+/*!
+Compact binary serialization of collapsed IP sets.
+
+Layout (all integers little-endian):
+
+| offset | size | field                                         |
+|--------|------|-----------------------------------------------|
+| 0      | 8    | magic `MINIPSET`                              |
+| 8      | 2    | format version (currently 1)                  |
+| 10     | 2    | flags (reserved, 0)                           |
+| 12     | 4    | CRC-32 (IEEE) of everything from offset 16 on |
+| 16     | 8    | number of IPv4 ranges (`n4`)                  |
+| 24     | 8    | number of IPv6 ranges (`n6`)                  |
+| 32     | 8×n4 | IPv4 ranges as `(beg: u32, end: u32)`         |
+| ...    | 32×n6| IPv6 ranges as `(beg: u128, end: u128)`       |
+
+Ranges are inclusive, sorted and disjoint, so membership can be answered by
+binary search directly on the encoded bytes (f.ex. an mmap'd file).
+*/
+
+use super::{
+    collapsing::{iprange_to_range, merge_ranges, range_to_cidrs},
+    prelude::*,
+    strings::*,
+    structs::{Cidr, IpFam, IpRange, Range},
+    AddressError,
+};
+use core::{
+    convert::Infallible,
+    error, fmt,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+};
+#[cfg(feature = "std")]
+use std::io::{self, Write};
+
+const MAGIC: &[u8; 8] = b"MINIPSET";
+const VERSION: u16 = 1;
+const HEADER_LEN: usize = 32;
+const V4_REC_LEN: usize = 8;
+const V6_REC_LEN: usize = 32;
+const COMMENT: char = '#';
+
+#[derive(Debug)]
+pub enum IpSetError {
+    /// writing the encoded set failed
+    #[cfg(feature = "std")]
+    Io(io::Error),
+    /// an input item was not a valid address/range
+    Address(AddressError),
+    /// a line of text input could not be parsed (1-based line number)
+    Line {
+        line: usize,
+        source: AddressError,
+    },
+    BadMagic,
+    Version(u16),
+    /// buffer is shorter/longer than the header says
+    Length {
+        expected: usize,
+        actual: usize,
+    },
+    Checksum {
+        expected: u32,
+        actual: u32,
+    },
+    /// ranges are not sorted and disjoint
+    Order,
+}
+
+impl fmt::Display for IpSetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            IpSetError::Io(_) => f.write_str(ERR_BIN_IO),
+            IpSetError::Address(e) => write!(f, "{e}"),
+            IpSetError::Line { line, .. } => write!(f, "{ERR_BIN_LINE} {line}"),
+            IpSetError::BadMagic => write!(f, "{ERR_BIN_MAGIC}"),
+            IpSetError::Version(v) => write!(f, "{ERR_BIN_VERSION}: {v}"),
+            IpSetError::Length { expected, actual } => {
+                write!(
+                    f,
+                    "{ERR_BIN_LENGTH}: expected {expected}, got {actual} bytes"
+                )
+            }
+            IpSetError::Checksum { expected, actual } => {
+                write!(
+                    f,
+                    "{ERR_BIN_CHECKSUM}: expected {expected:#010x}, got {actual:#010x}"
+                )
+            }
+            IpSetError::Order => write!(f, "{ERR_BIN_ORDER}"),
+        }
+    }
+}
+
+impl error::Error for IpSetError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "std")]
+            IpSetError::Io(e) => Some(e),
+            // transparent: displayed as the address error itself
+            IpSetError::Address(e) => e.source(),
+            IpSetError::Line { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for IpSetError {
+    fn from(e: io::Error) -> Self {
+        IpSetError::Io(e)
+    }
+}
+
+impl From<AddressError> for IpSetError {
+    fn from(e: AddressError) -> Self {
+        IpSetError::Address(e)
+    }
+}
+
+/* ---------------------------------- */
+
+/**
+Encode IPs, CIDRs or ranges into the binary IP set format.
+
+The input is collapsed first, so the result is the minimal representation
+regardless of overlaps or ordering in `input`.
+
+The format has no room for IPv6 zones: zoned entries are rejected with
+[AddressError::InvalidZone], use [strip_zones](super::strip_zones) first
+to store them anyway.
+*/
+pub fn encode_ipset<I, T>(input: I) -> Result<Vec<u8>, AddressError>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<IpRange>,
+{
+    let (v4, v6) = collapse_by_family(input)?;
+    let mut out: Vec<u8> = Vec::with_capacity(encoded_len(v4.len(), v6.len()));
+    let Ok(()) = write_encoded(&v4, &v6, |b: &[u8]| {
+        out.extend_from_slice(b);
+        Ok::<(), Infallible>(())
+    });
+    Ok(out)
+}
+
+/// Like [encode_ipset], but writes the encoded set into `w`.
+#[cfg(feature = "std")]
+pub fn write_ipset<I, T, W>(input: I, mut w: W) -> Result<(), IpSetError>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<IpRange>,
+    W: Write,
+{
+    let (v4, v6) = collapse_by_family(input)?;
+    write_encoded(&v4, &v6, |b: &[u8]| w.write_all(b))?;
+    Ok(w.flush()?)
+}
+
+/**
+Parse a text list (one IP, CIDR or range per line) into the binary format.
+
+Blank lines and `#` comments (also trailing ones) are ignored. Errors carry
+the 1-based line number of the offending entry.
+*/
+pub fn text_to_ipset(text: &str) -> Result<Vec<u8>, IpSetError> {
+    let mut entries: Vec<IpRange> = Vec::new();
+    for (idx, line) in text.lines().enumerate() {
+        let entry: &str = line.split(COMMENT).next().unwrap_or_default().trim();
+        if entry.is_empty() {
+            continue;
+        }
+        let range: IpRange = entry
+            .parse::<IpRange>()
+            .map_err(|source| IpSetError::Line {
+                line: idx + 1,
+                source,
+            })?;
+        entries.push(range);
+    }
+    Ok(encode_ipset(entries)?)
+}
+
+/// Decode a binary IP set into text: its minimal CIDRs, one per line.
+pub fn ipset_to_text(bytes: &[u8]) -> Result<String, IpSetError> {
+    let view: IpSetView = IpSetView::from_bytes(bytes)?;
+    let mut out: String = String::new();
+    for cidr in view.to_cidrs() {
+        out.push_str(&cidr.to_string());
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// Collapse `input` into sorted, disjoint ranges, split per address family.
+fn collapse_by_family<I, T>(input: I) -> Result<(Vec<Range>, Vec<Range>), AddressError>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<IpRange>,
+{
+    let mut ranges: Vec<Range> = input
+        .into_iter()
+        .map(|item| match item.into() {
+            IpRange { zone: Some(z), .. } => Err(AddressError::InvalidZone(z.to_string())),
+            r => iprange_to_range(r),
+        })
+        .collect::<Result<_, _>>()?;
+    ranges.sort_by_key(Range::cmp_key);
+    let (v4, v6): (Vec<Range>, Vec<Range>) = merge_ranges(&ranges)
+        .into_iter()
+        .partition(|r| r.fam == IpFam::V4);
+    Ok((v4, v6))
+}
+
+#[inline]
+fn encoded_len(n4: usize, n6: usize) -> usize {
+    HEADER_LEN + n4 * V4_REC_LEN + n6 * V6_REC_LEN
+}
+
+/// Feed the encoded set to `put` in pieces.
+fn write_encoded<E, F>(v4: &[Range], v6: &[Range], mut put: F) -> Result<(), E>
+where
+    F: FnMut(&[u8]) -> Result<(), E>,
+{
+    let counts: [u8; 16] = {
+        let mut c: [u8; 16] = [0; 16];
+        c[..8].copy_from_slice(&(v4.len() as u64).to_le_bytes());
+        c[8..].copy_from_slice(&(v6.len() as u64).to_le_bytes());
+        c
+    };
+
+    // The checksum goes into the header, so compute it before writing anything
+    let mut crc: Crc32 = Crc32::new();
+    crc.update(&counts);
+    for r in v4 {
+        crc.update(&v4_record(r));
+    }
+    for r in v6 {
+        crc.update(&v6_record(r));
+    }
+
+    put(MAGIC)?;
+    put(&VERSION.to_le_bytes())?;
+    put(&0u16.to_le_bytes())?;
+    put(&crc.finish().to_le_bytes())?;
+    put(&counts)?;
+    for r in v4 {
+        put(&v4_record(r))?;
+    }
+    for r in v6 {
+        put(&v6_record(r))?;
+    }
+    Ok(())
+}
+
+#[inline]
+fn v4_record(r: &Range) -> [u8; V4_REC_LEN] {
+    let mut rec: [u8; V4_REC_LEN] = [0; V4_REC_LEN];
+    rec[..4].copy_from_slice(&(r.beg as u32).to_le_bytes());
+    rec[4..].copy_from_slice(&(r.end as u32).to_le_bytes());
+    rec
+}
+
+#[inline]
+fn v6_record(r: &Range) -> [u8; V6_REC_LEN] {
+    let mut rec: [u8; V6_REC_LEN] = [0; V6_REC_LEN];
+    rec[..16].copy_from_slice(&r.beg.to_le_bytes());
+    rec[16..].copy_from_slice(&r.end.to_le_bytes());
+    rec
+}
+
+/* ---------------------------------- */
+
+/**
+Zero-copy view over an encoded IP set.
+
+Nothing is decoded up front: [IpSetView::contains] does a binary search
+straight on the borrowed bytes, which makes it suitable for mmap'd files.
+*/
+#[derive(Clone, Copy, Debug)]
+pub struct IpSetView<'a> {
+    v4: &'a [u8],
+    v6: &'a [u8],
+}
+
+impl<'a> IpSetView<'a> {
+    /// Open an encoded set, verifying header, length, checksum and range order.
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<Self, IpSetError> {
+        let view: IpSetView = Self::from_bytes_unchecked(bytes)?;
+
+        let expected: u32 = u32::from_le_bytes(bytes[12..16].try_into().unwrap());
+        let actual: u32 = {
+            let mut crc: Crc32 = Crc32::new();
+            crc.update(&bytes[16..]);
+            crc.finish()
+        };
+        if expected != actual {
+            return Err(IpSetError::Checksum { expected, actual });
+        }
+
+        if !view.is_ordered() {
+            return Err(IpSetError::Order);
+        }
+        Ok(view)
+    }
+
+    /**
+    Open an encoded set, verifying only the header and length.
+
+    Skips the checksum and ordering checks (both are O(n)). Lookups on a
+    corrupt set give wrong answers, but never panic.
+    */
+    pub fn from_bytes_unchecked(bytes: &'a [u8]) -> Result<Self, IpSetError> {
+        if bytes.len() < HEADER_LEN {
+            return Err(IpSetError::Length {
+                expected: HEADER_LEN,
+                actual: bytes.len(),
+            });
+        }
+        if &bytes[..8] != MAGIC {
+            return Err(IpSetError::BadMagic);
+        }
+        let version: u16 = u16::from_le_bytes(bytes[8..10].try_into().unwrap());
+        if version != VERSION {
+            return Err(IpSetError::Version(version));
+        }
+
+        let n4: u64 = u64::from_le_bytes(bytes[16..24].try_into().unwrap());
+        let n6: u64 = u64::from_le_bytes(bytes[24..32].try_into().unwrap());
+        let expected: usize = usize::try_from(n4)
+            .ok()
+            .zip(usize::try_from(n6).ok())
+            .and_then(|(n4, n6)| {
+                let v4: usize = n4.checked_mul(V4_REC_LEN)?;
+                let v6: usize = n6.checked_mul(V6_REC_LEN)?;
+                HEADER_LEN.checked_add(v4)?.checked_add(v6)
+            })
+            .unwrap_or(usize::MAX);
+        if bytes.len() != expected {
+            return Err(IpSetError::Length {
+                expected,
+                actual: bytes.len(),
+            });
+        }
+
+        let v4_end: usize = HEADER_LEN + n4 as usize * V4_REC_LEN;
+        Ok(Self {
+            v4: &bytes[HEADER_LEN..v4_end],
+            v6: &bytes[v4_end..],
+        })
+    }
+
+    /// Whether `ip` is a member of the set. O(log n).
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match ip {
+            IpAddr::V4(a) => search(self.num_v4(), |i| self.v4_at(i), u32::from(a) as u128),
+            IpAddr::V6(a) => search(self.num_v6(), |i| self.v6_at(i), u128::from(a)),
+        }
+    }
+
+    /// Number of IPv4 ranges in the set.
+    pub fn num_v4(&self) -> usize {
+        self.v4.len() / V4_REC_LEN
+    }
+
+    /// Number of IPv6 ranges in the set.
+    pub fn num_v6(&self) -> usize {
+        self.v6.len() / V6_REC_LEN
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.v4.is_empty() && self.v6.is_empty()
+    }
+
+    /// Iterate over the stored ranges (IPv4 first), decoding them on the fly.
+    pub fn ranges(&self) -> impl Iterator<Item = IpRange> + 'a {
+        let view: IpSetView<'a> = *self;
+        let v4 = (0..view.num_v4()).map(move |i| {
+            let (beg, end) = view.v4_at(i);
+            IpRange {
+                beg: IpAddr::V4(Ipv4Addr::from(beg as u32)),
+                end: IpAddr::V4(Ipv4Addr::from(end as u32)),
+                zone: None,
+            }
+        });
+        let v6 = (0..view.num_v6()).map(move |i| {
+            let (beg, end) = view.v6_at(i);
+            IpRange {
+                beg: IpAddr::V6(Ipv6Addr::from(beg)),
+                end: IpAddr::V6(Ipv6Addr::from(end)),
+                zone: None,
+            }
+        });
+        v4.chain(v6)
+    }
+
+    /// Decode the set into its minimal CIDRs.
+    pub fn to_cidrs(&self) -> Vec<Cidr> {
+        let mut out: Vec<Cidr> = Vec::new();
+        for i in 0..self.num_v4() {
+            let (beg, end) = self.v4_at(i);
+            out.extend(range_to_cidrs(Range {
+                fam: IpFam::V4,
+                zone: None,
+                beg,
+                end,
+            }));
+        }
+        for i in 0..self.num_v6() {
+            let (beg, end) = self.v6_at(i);
+            out.extend(range_to_cidrs(Range {
+                fam: IpFam::V6,
+                zone: None,
+                beg,
+                end,
+            }));
+        }
+        out
+    }
+
+    #[inline]
+    fn v4_at(&self, i: usize) -> (u128, u128) {
+        let rec: &[u8] = &self.v4[i * V4_REC_LEN..(i + 1) * V4_REC_LEN];
+        let beg: u32 = u32::from_le_bytes(rec[..4].try_into().unwrap());
+        let end: u32 = u32::from_le_bytes(rec[4..].try_into().unwrap());
+        (beg as u128, end as u128)
+    }
+
+    #[inline]
+    fn v6_at(&self, i: usize) -> (u128, u128) {
+        let rec: &[u8] = &self.v6[i * V6_REC_LEN..(i + 1) * V6_REC_LEN];
+        let beg: u128 = u128::from_le_bytes(rec[..16].try_into().unwrap());
+        let end: u128 = u128::from_le_bytes(rec[16..].try_into().unwrap());
+        (beg, end)
+    }
+
+    /// Every range is well-formed, and they are sorted and disjoint.
+    fn is_ordered(&self) -> bool {
+        let ok = |n: usize, at: &dyn Fn(usize) -> (u128, u128)| {
+            (0..n).all(|i| {
+                let (beg, end) = at(i);
+                beg <= end && (i == 0 || at(i - 1).1 < beg)
+            })
+        };
+        ok(self.num_v4(), &|i| self.v4_at(i)) && ok(self.num_v6(), &|i| self.v6_at(i))
+    }
+}
+
+/// Binary search for `x` in `n` sorted, disjoint ranges accessed through `at`.
+#[inline]
+fn search(n: usize, at: impl Fn(usize) -> (u128, u128), x: u128) -> bool {
+    // find the number of ranges starting at or below x
+    let (mut lo, mut hi) = (0usize, n);
+    while lo < hi {
+        let mid: usize = lo + (hi - lo) / 2;
+        if at(mid).0 <= x {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+    lo > 0 && x <= at(lo - 1).1
+}
+
+/* ---------------------------------- */
+
+/// CRC-32 (IEEE 802.3) lookup table, generated at compile time.
+const CRC_TABLE: [u32; 256] = {
+    let mut table: [u32; 256] = [0; 256];
+    let mut i: usize = 0;
+    while i < 256 {
+        let mut c: u32 = i as u32;
+        let mut k: u8 = 0;
+        while k < 8 {
+            c = if c & 1 != 0 {
+                0xEDB8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+};
+
+/// Incremental CRC-32 hasher.
+struct Crc32(u32);
+
+impl Crc32 {
+    fn new() -> Self {
+        Self(!0)
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        for &b in data {
+            self.0 = CRC_TABLE[((self.0 ^ b as u32) & 0xff) as usize] ^ (self.0 >> 8);
+        }
+    }
+
+    fn finish(&self) -> u32 {
+        !self.0
+    }
+}
+
+/* -------------------------------------------------------------------------- */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "\
+        # test set\n\
+        10.0.0.0/24\n\
+        10.0.1.0/24   # adjacent, merges\n\
+        \n\
+        192.168.1.10-20\n\
+        2001:db8::/64\n";
+    const COLLAPSED: &str = "10.0.0.0/23\n192.168.1.10/31\n192.168.1.12/30\n192.168.1.16/30\n192.168.1.20/32\n2001:db8::/64\n";
+    const INSIDE: [&str; 4] = ["10.0.0.0", "10.0.1.255", "192.168.1.15", "2001:db8::ffff"];
+    const OUTSIDE: [&str; 5] = [
+        "9.255.255.255",
+        "10.0.2.0",
+        "192.168.1.21",
+        "::1",
+        "2001:db8:0:1::",
+    ];
+
+    #[test]
+    fn test_crc32() {
+        let mut crc: Crc32 = Crc32::new();
+        crc.update(b"123456789");
+        assert_eq!(crc.finish(), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_roundtrip_and_contains() {
+        let bytes: Vec<u8> = text_to_ipset(TEXT).unwrap();
+        assert_eq!(bytes.len(), encoded_len(2, 1));
+
+        let view: IpSetView = IpSetView::from_bytes(&bytes).unwrap();
+        assert_eq!((view.num_v4(), view.num_v6()), (2, 1));
+        for ip in INSIDE {
+            assert!(view.contains(ip.parse().unwrap()), "{ip}");
+        }
+        for ip in OUTSIDE {
+            assert!(!view.contains(ip.parse().unwrap()), "{ip}");
+        }
+        assert_eq!(ipset_to_text(&bytes).unwrap(), COLLAPSED);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_write_matches_encode() {
+        let input: Vec<Cidr> = vec!["10.0.0.0/8".parse().unwrap(), "::/0".parse().unwrap()];
+        let mut written: Vec<u8> = Vec::new();
+        write_ipset(input.iter().copied(), &mut written).unwrap();
+        assert_eq!(written, encode_ipset(input.iter().copied()).unwrap());
+        assert_eq!(IpSetView::from_bytes(&written).unwrap().to_cidrs(), input);
+    }
+
+    #[test]
+    fn test_line_errors() {
+        let err: IpSetError = text_to_ipset("10.0.0.1\n\n10.0.0.300\n").unwrap_err();
+        assert!(matches!(err, IpSetError::Line { line: 3, .. }));
+        // the address error is the source, not part of the message
+        assert_eq!(err.to_string(), format!("{ERR_BIN_LINE} 3"));
+        assert!(error::Error::source(&err).unwrap().is::<AddressError>());
+        // zones cannot be stored
+        assert!(matches!(
+            text_to_ipset("fe80::1%eth0\n"),
+            Err(IpSetError::Address(AddressError::InvalidZone(_)))
+        ));
+    }
+
+    #[test]
+    fn test_corruption_detected() {
+        let bytes: Vec<u8> = text_to_ipset(TEXT).unwrap();
+
+        let mut bad: Vec<u8> = bytes.clone();
+        bad[HEADER_LEN] ^= 0x01;
+        assert!(matches!(
+            IpSetView::from_bytes(&bad),
+            Err(IpSetError::Checksum { .. })
+        ));
+        assert!(IpSetView::from_bytes_unchecked(&bad).is_ok());
+
+        let mut bad: Vec<u8> = bytes.clone();
+        bad[0] = b'X';
+        assert!(matches!(
+            IpSetView::from_bytes(&bad),
+            Err(IpSetError::BadMagic)
+        ));
+
+        assert!(matches!(
+            IpSetView::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(IpSetError::Length { .. })
+        ));
+    }
+}
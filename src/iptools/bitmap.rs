@@ -0,0 +1,510 @@
+// Copyright (c) 2026 Mikko Tanner. All rights reserved.
+// Licensed under the MIT License or the Apache License, Version 2.0.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+/*!
+Compressed bitmap of IPv4 addresses, in the style of Roaring bitmaps.
+
+The address space is split by the upper 16 bits into /16 containers. Each
+container stores the lower 16 bits of its addresses in whichever encoding
+is smallest: a sorted array (sparse), a 64 Kibit bitmap (dense), or a list
+of runs (contiguous blocks).
+*/
+
+use super::{
+    collapsing::{range_to_cidrs, sorted_merged},
+    prelude::*,
+    structs::{Cidr, IpFam, Range},
+};
+use core::{mem, net::Ipv4Addr};
+
+/// Arrays switch to bitmaps beyond this many entries (at 8 KiB, same as a bitmap).
+const ARRAY_MAX: usize = 4096;
+const BITMAP_WORDS: usize = 1024;
+/// Size of a bitmap container in bytes.
+const BITMAP_BYTES: usize = BITMAP_WORDS * 8;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Container {
+    /// sorted lower 16 bits, at most [ARRAY_MAX]
+    Array(Vec<u16>),
+    Bitmap(Box<[u64; BITMAP_WORDS]>),
+    /// sorted, disjoint and non-adjacent inclusive runs
+    Run(Vec<(u16, u16)>),
+}
+
+impl Container {
+    fn contains(&self, low: u16) -> bool {
+        match self {
+            Container::Array(v) => v.binary_search(&low).is_ok(),
+            Container::Bitmap(b) => b[low as usize / 64] & (1 << (low % 64)) != 0,
+            Container::Run(runs) => {
+                // the last run starting at or before `low`
+                let i: usize = runs.partition_point(|(beg, _)| *beg <= low);
+                i > 0 && runs[i - 1].1 >= low
+            }
+        }
+    }
+
+    /// Returns whether `low` was not present before.
+    fn insert(&mut self, low: u16) -> bool {
+        match self {
+            Container::Array(v) => match v.binary_search(&low) {
+                Ok(_) => false,
+                Err(i) => {
+                    v.insert(i, low);
+                    if v.len() > ARRAY_MAX {
+                        *self = Container::Bitmap(to_bitmap(self));
+                    }
+                    true
+                }
+            },
+            Container::Bitmap(b) => {
+                let (word, bit): (usize, u64) = (low as usize / 64, 1 << (low % 64));
+                let new: bool = b[word] & bit == 0;
+                b[word] |= bit;
+                new
+            }
+            Container::Run(_) => {
+                if self.contains(low) {
+                    return false;
+                }
+                let Container::Run(runs) = self else {
+                    unreachable!()
+                };
+                let i: usize = runs.partition_point(|(beg, _)| *beg <= low);
+                let joins_prev: bool = i > 0 && runs[i - 1].1.checked_add(1) == Some(low);
+                let joins_next: bool = i < runs.len() && low.checked_add(1) == Some(runs[i].0);
+                match (joins_prev, joins_next) {
+                    (true, true) => {
+                        runs[i - 1].1 = runs[i].1;
+                        runs.remove(i);
+                    }
+                    (true, false) => runs[i - 1].1 = low,
+                    (false, true) => runs[i].0 = low,
+                    (false, false) => runs.insert(i, (low, low)),
+                }
+                self.optimize();
+                true
+            }
+        }
+    }
+
+    fn len(&self) -> u32 {
+        match self {
+            Container::Array(v) => v.len() as u32,
+            Container::Bitmap(b) => b.iter().map(|w| w.count_ones()).sum(),
+            Container::Run(runs) => runs.iter().map(|(b, e)| (e - b) as u32 + 1).sum(),
+        }
+    }
+
+    /// Heap memory used, in bytes.
+    fn heap_size(&self) -> usize {
+        match self {
+            Container::Array(v) => v.capacity() * mem::size_of::<u16>(),
+            Container::Bitmap(_) => BITMAP_BYTES,
+            Container::Run(runs) => runs.capacity() * mem::size_of::<(u16, u16)>(),
+        }
+    }
+
+    /// Switch to the smallest encoding for the current contents.
+    fn optimize(&mut self) {
+        let runs: Vec<(u16, u16)> = to_runs(self);
+        let len: usize = self.len() as usize;
+        let run_bytes: usize = runs.len() * mem::size_of::<(u16, u16)>();
+        let array_bytes: usize = len * mem::size_of::<u16>();
+        *self = if run_bytes < array_bytes.min(BITMAP_BYTES) {
+            Container::Run(runs)
+        } else if len <= ARRAY_MAX {
+            Container::Array(self.iter().collect())
+        } else {
+            Container::Bitmap(to_bitmap(self))
+        };
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = u16> + '_> {
+        match self {
+            Container::Array(v) => Box::new(v.iter().copied()),
+            Container::Bitmap(b) => Box::new(b.iter().enumerate().flat_map(|(i, w)| {
+                let mut w: u64 = *w;
+                core::iter::from_fn(move || {
+                    (w != 0).then(|| {
+                        let bit: u32 = w.trailing_zeros();
+                        w &= w - 1;
+                        (i * 64) as u16 + bit as u16
+                    })
+                })
+            })),
+            Container::Run(runs) => Box::new(runs.iter().flat_map(|(b, e)| *b..=*e)),
+        }
+    }
+
+    fn union(&self, other: &Container) -> Container {
+        let mut out: Container = match (self, other) {
+            (Container::Bitmap(_), _) | (_, Container::Bitmap(_)) => {
+                let (mut a, b) = (to_bitmap(self), to_bitmap(other));
+                a.iter_mut().zip(b.iter()).for_each(|(x, y)| *x |= y);
+                Container::Bitmap(a)
+            }
+            (Container::Array(a), Container::Array(b)) => {
+                let mut v: Vec<u16> = Vec::with_capacity(a.len() + b.len());
+                v.extend_from_slice(a);
+                v.extend_from_slice(b);
+                v.sort_unstable();
+                v.dedup();
+                match v.len() > ARRAY_MAX {
+                    true => Container::Bitmap(to_bitmap(&Container::Array(v))),
+                    false => Container::Array(v),
+                }
+            }
+            _ => {
+                let mut runs: Vec<(u16, u16)> = to_runs(self);
+                runs.extend(to_runs(other));
+                runs.sort_unstable();
+                Container::Run(merge_runs(runs))
+            }
+        };
+        out.optimize();
+        out
+    }
+
+    fn intersection(&self, other: &Container) -> Container {
+        let mut out: Container = match (self, other) {
+            (Container::Array(a), c) | (c, Container::Array(a)) => {
+                Container::Array(a.iter().copied().filter(|x| c.contains(*x)).collect())
+            }
+            (Container::Run(a), Container::Run(b)) => {
+                let (mut i, mut j) = (0usize, 0usize);
+                let mut runs: Vec<(u16, u16)> = Vec::new();
+                while i < a.len() && j < b.len() {
+                    let (beg, end) = (a[i].0.max(b[j].0), a[i].1.min(b[j].1));
+                    if beg <= end {
+                        runs.push((beg, end));
+                    }
+                    if a[i].1 < b[j].1 {
+                        i += 1;
+                    } else {
+                        j += 1;
+                    }
+                }
+                Container::Run(runs)
+            }
+            _ => {
+                let (mut a, b) = (to_bitmap(self), to_bitmap(other));
+                a.iter_mut().zip(b.iter()).for_each(|(x, y)| *x &= y);
+                Container::Bitmap(a)
+            }
+        };
+        out.optimize();
+        out
+    }
+}
+
+fn to_bitmap(c: &Container) -> Box<[u64; BITMAP_WORDS]> {
+    if let Container::Bitmap(b) = c {
+        return b.clone();
+    }
+    let mut b: Box<[u64; BITMAP_WORDS]> = Box::new([0; BITMAP_WORDS]);
+    for low in c.iter() {
+        b[low as usize / 64] |= 1 << (low % 64);
+    }
+    b
+}
+
+fn to_runs(c: &Container) -> Vec<(u16, u16)> {
+    if let Container::Run(runs) = c {
+        return runs.clone();
+    }
+    let mut runs: Vec<(u16, u16)> = Vec::new();
+    for low in c.iter() {
+        match runs.last_mut() {
+            Some((_, end)) if *end + 1 == low => *end = low,
+            _ => runs.push((low, low)),
+        }
+    }
+    runs
+}
+
+/// Merge sorted runs which overlap or touch.
+fn merge_runs(sorted: Vec<(u16, u16)>) -> Vec<(u16, u16)> {
+    let mut out: Vec<(u16, u16)> = Vec::with_capacity(sorted.len());
+    for (beg, end) in sorted {
+        match out.last_mut() {
+            Some((_, last)) if beg as u32 <= *last as u32 + 1 => *last = (*last).max(end),
+            _ => out.push((beg, end)),
+        }
+    }
+    out
+}
+
+/* ---------------------------------- */
+
+/**
+Compressed set of IPv4 addresses.
+
+Lookups are a binary search over at most 65536 containers plus one inside
+the container. Memory use depends on the data: about 2 bytes per address
+for scattered hosts, at most 8 KiB per /16, and 4 bytes per contiguous
+block; compare to 20+ bytes per address in a `HashSet<Ipv4Addr>`.
+*/
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Ipv4Bitmap {
+    /// sorted by the upper 16 bits, never empty containers
+    containers: Vec<(u16, Container)>,
+}
+
+impl Ipv4Bitmap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+    Build from CIDRs, f.ex. the output of [collapse_ips](super::collapse_ips).
+    IPv6 entries are ignored.
+    */
+    pub fn from_cidrs(cidrs: &[Cidr]) -> Self {
+        let v4: Vec<Cidr> = cidrs.iter().filter(|c| c.is_ipv4()).copied().collect();
+        let mut out: Ipv4Bitmap = Ipv4Bitmap::new();
+        // merged ranges are sorted, so containers and their runs come in order
+        for r in sorted_merged(&v4) {
+            let (beg, end): (u32, u32) = (r.beg as u32, r.end as u32);
+            for key in (beg >> 16)..=(end >> 16) {
+                let lo: u16 = if key == beg >> 16 { beg as u16 } else { 0 };
+                let hi: u16 = if key == end >> 16 {
+                    end as u16
+                } else {
+                    u16::MAX
+                };
+                match out.containers.last_mut() {
+                    Some((k, Container::Run(runs))) if *k == key as u16 => runs.push((lo, hi)),
+                    _ => out
+                        .containers
+                        .push((key as u16, Container::Run(vec![(lo, hi)]))),
+                }
+            }
+        }
+        out.containers.iter_mut().for_each(|(_, c)| c.optimize());
+        out
+    }
+
+    /// The minimal list of CIDRs covering exactly this set, as [collapse_ips](super::collapse_ips) would give.
+    pub fn to_cidrs(&self) -> Vec<Cidr> {
+        let mut ranges: Vec<Range> = Vec::new();
+        for (key, c) in &self.containers {
+            let base: u128 = (*key as u128) << 16;
+            for (beg, end) in to_runs(c) {
+                let (beg, end): (u128, u128) = (base + beg as u128, base + end as u128);
+                match ranges.last_mut() {
+                    Some(last) if last.end + 1 == beg => last.end = end,
+                    _ => ranges.push(Range {
+                        fam: IpFam::V4,
+                        zone: None,
+                        beg,
+                        end,
+                    }),
+                }
+            }
+        }
+        ranges.into_iter().flat_map(range_to_cidrs).collect()
+    }
+
+    pub fn contains(&self, ip: Ipv4Addr) -> bool {
+        let (key, low): (u16, u16) = split(ip);
+        match self.containers.binary_search_by_key(&key, |(k, _)| *k) {
+            Ok(i) => self.containers[i].1.contains(low),
+            Err(_) => false,
+        }
+    }
+
+    /// Returns whether `ip` was not in the set before.
+    pub fn insert(&mut self, ip: Ipv4Addr) -> bool {
+        let (key, low): (u16, u16) = split(ip);
+        match self.containers.binary_search_by_key(&key, |(k, _)| *k) {
+            Ok(i) => self.containers[i].1.insert(low),
+            Err(i) => {
+                self.containers
+                    .insert(i, (key, Container::Array(vec![low])));
+                true
+            }
+        }
+    }
+
+    /// Number of addresses in the set (the cardinality).
+    pub fn len(&self) -> u64 {
+        self.containers.iter().map(|(_, c)| c.len() as u64).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.containers.is_empty()
+    }
+
+    /// Approximate heap memory used, in bytes.
+    pub fn heap_size(&self) -> usize {
+        self.containers.capacity() * mem::size_of::<(u16, Container)>()
+            + self
+                .containers
+                .iter()
+                .map(|(_, c)| c.heap_size())
+                .sum::<usize>()
+    }
+
+    /// Addresses in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = Ipv4Addr> + '_ {
+        self.containers.iter().flat_map(|(key, c)| {
+            c.iter()
+                .map(move |low| Ipv4Addr::from(((*key as u32) << 16) | low as u32))
+        })
+    }
+
+    pub fn union(&self, other: &Ipv4Bitmap) -> Ipv4Bitmap {
+        let (a, b) = (&self.containers, &other.containers);
+        let mut out: Vec<(u16, Container)> = Vec::with_capacity(a.len().max(b.len()));
+        let (mut i, mut j) = (0usize, 0usize);
+        while i < a.len() || j < b.len() {
+            let next: (u16, Container) = match (a.get(i), b.get(j)) {
+                (Some(x), Some(y)) if x.0 == y.0 => {
+                    i += 1;
+                    j += 1;
+                    (x.0, x.1.union(&y.1))
+                }
+                (Some(x), Some(y)) if x.0 < y.0 => {
+                    i += 1;
+                    x.clone()
+                }
+                (Some(x), None) => {
+                    i += 1;
+                    x.clone()
+                }
+                (_, Some(y)) => {
+                    j += 1;
+                    y.clone()
+                }
+                (None, None) => unreachable!("loop condition"),
+            };
+            out.push(next);
+        }
+        Ipv4Bitmap { containers: out }
+    }
+
+    pub fn intersection(&self, other: &Ipv4Bitmap) -> Ipv4Bitmap {
+        let (a, b) = (&self.containers, &other.containers);
+        let mut out: Vec<(u16, Container)> = Vec::new();
+        let (mut i, mut j) = (0usize, 0usize);
+        while i < a.len() && j < b.len() {
+            match a[i].0.cmp(&b[j].0) {
+                core::cmp::Ordering::Less => i += 1,
+                core::cmp::Ordering::Greater => j += 1,
+                core::cmp::Ordering::Equal => {
+                    let c: Container = a[i].1.intersection(&b[j].1);
+                    if c.len() > 0 {
+                        out.push((a[i].0, c));
+                    }
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        Ipv4Bitmap { containers: out }
+    }
+}
+
+impl FromIterator<Ipv4Addr> for Ipv4Bitmap {
+    fn from_iter<I: IntoIterator<Item = Ipv4Addr>>(iter: I) -> Self {
+        let mut out: Ipv4Bitmap = Ipv4Bitmap::new();
+        for ip in iter {
+            out.insert(ip);
+        }
+        out
+    }
+}
+
+#[inline]
+fn split(ip: Ipv4Addr) -> (u16, u16) {
+    let v: u32 = u32::from(ip);
+    ((v >> 16) as u16, v as u16)
+}
+
+/* -------------------------------------------------------------------------- */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::iptools::collapse_ips;
+    use alloc::collections::BTreeSet;
+    use core::net::IpAddr;
+
+    /// Deterministic scattered addresses (xorshift), `mask` limits the spread.
+    fn scattered(n: usize, seed: u32, mask: u32) -> Vec<Ipv4Addr> {
+        let mut x: u32 = seed;
+        (0..n)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 17;
+                x ^= x << 5;
+                Ipv4Addr::from(0x0a00_0000 | (x & mask))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_insert_contains_encodings() {
+        let mut set: Ipv4Bitmap = Ipv4Bitmap::new();
+        let ips: Vec<Ipv4Addr> = scattered(10_000, 1, 0xffff);
+        let reference: BTreeSet<Ipv4Addr> = ips.iter().copied().collect();
+        for ip in &ips {
+            set.insert(*ip);
+        }
+        // more than ARRAY_MAX in one /16
+        assert!(matches!(set.containers[0].1, Container::Bitmap(_)));
+        assert_eq!(set.len(), reference.len() as u64);
+        assert!(set.iter().eq(reference.iter().copied()));
+        assert!(!set.contains("10.1.0.0".parse().unwrap()));
+        assert!(!set.insert(ips[0]));
+
+        // a contiguous block is a single run
+        let block: Ipv4Bitmap = Ipv4Bitmap::from_cidrs(&["192.168.0.0/17".parse().unwrap()]);
+        assert!(matches!(&block.containers[0].1, Container::Run(r) if r.len() == 1));
+        assert_eq!(block.len(), 1 << 15);
+        assert!(block.heap_size() < 256);
+        assert!(block.contains("192.168.127.255".parse().unwrap()));
+        assert!(!block.contains("192.168.128.0".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let (a, b): (Vec<Ipv4Addr>, Vec<Ipv4Addr>) =
+            (scattered(5_000, 7, 0x3_ffff), scattered(5_000, 9, 0x3_ffff));
+        let (ra, rb): (BTreeSet<Ipv4Addr>, BTreeSet<Ipv4Addr>) =
+            (a.iter().copied().collect(), b.iter().copied().collect());
+        let mut sa: Ipv4Bitmap = a.into_iter().collect();
+        let sb: Ipv4Bitmap = b.into_iter().collect();
+        // mix in a run container
+        sa = sa.union(&Ipv4Bitmap::from_cidrs(&["10.0.0.0/22".parse().unwrap()]));
+        let ra: BTreeSet<Ipv4Addr> = ra
+            .into_iter()
+            .chain((0..1024).map(|i| Ipv4Addr::from(0x0a00_0000 + i)))
+            .collect();
+
+        assert!(sa.union(&sb).iter().eq(ra.union(&rb).copied()));
+        assert!(sa
+            .intersection(&sb)
+            .iter()
+            .eq(ra.intersection(&rb).copied()));
+        assert_eq!(sa.intersection(&Ipv4Bitmap::new()).len(), 0);
+    }
+
+    #[test]
+    fn test_cidr_conversions() {
+        let ips: Vec<IpAddr> = scattered(2_000, 3, 0x0fff)
+            .into_iter()
+            .map(IpAddr::V4)
+            .chain(["10.0.255.255", "10.1.0.0", "2001:db8::1"].map(|s| s.parse().unwrap()))
+            .collect();
+        let cidrs: Vec<Cidr> = collapse_ips(&ips, 0);
+        let set: Ipv4Bitmap = Ipv4Bitmap::from_cidrs(&cidrs);
+        let v4: Vec<Cidr> = cidrs.into_iter().filter(|c| c.is_ipv4()).collect();
+        assert_eq!(set.to_cidrs(), v4);
+        // adjacent across a /16 boundary
+        assert!(set.to_cidrs().contains(&"10.0.255.255/32".parse().unwrap()));
+    }
+}
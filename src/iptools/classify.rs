@@ -0,0 +1,214 @@
+// Copyright (c) 2026 Mikko Tanner. All rights reserved.
+// Licensed under the MIT License or the Apache License, Version 2.0.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Address classification against the IANA special-purpose registries.
+
+use super::{
+    collapsing::{cidr_to_range, ip_to_host_cidr},
+    structs::{Cidr, IpFam, Range},
+};
+use core::{
+    fmt,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// What an address is used for, see [classify_ip].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum AddressKind {
+    /// `0.0.0.0`, `::`
+    Unspecified,
+    /// `0.0.0.0/8` ("this network")
+    ThisNetwork,
+    Loopback,
+    /// RFC 1918
+    Private,
+    /// `100.64.0.0/10` (CGNAT)
+    SharedAddress,
+    LinkLocal,
+    /// IETF protocol assignments, `192.0.0.0/24` and `2001::/23`
+    ProtocolAssignment,
+    Documentation,
+    /// `198.18.0.0/15`
+    Benchmarking,
+    Multicast,
+    /// `255.255.255.255`
+    Broadcast,
+    /// `fc00::/7` (ULA)
+    UniqueLocal,
+    /// `::ffff:0:0/96`
+    Ipv4Mapped,
+    /// NAT64 prefixes `64:ff9b::/96` and `64:ff9b:1::/48`
+    Translation,
+    /// `100::/64` (discard-only)
+    Discard,
+    /// reserved or unallocated by IANA
+    Reserved,
+    /// globally routable
+    Global,
+}
+
+impl AddressKind {
+    #[inline]
+    pub fn is_global(&self) -> bool {
+        *self == AddressKind::Global
+    }
+}
+
+impl fmt::Display for AddressKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s: &str = match self {
+            AddressKind::Unspecified => "unspecified",
+            AddressKind::ThisNetwork => "this-network",
+            AddressKind::Loopback => "loopback",
+            AddressKind::Private => "private",
+            AddressKind::SharedAddress => "shared-address",
+            AddressKind::LinkLocal => "link-local",
+            AddressKind::ProtocolAssignment => "protocol-assignment",
+            AddressKind::Documentation => "documentation",
+            AddressKind::Benchmarking => "benchmarking",
+            AddressKind::Multicast => "multicast",
+            AddressKind::Broadcast => "broadcast",
+            AddressKind::UniqueLocal => "unique-local",
+            AddressKind::Ipv4Mapped => "ipv4-mapped",
+            AddressKind::Translation => "translation",
+            AddressKind::Discard => "discard",
+            AddressKind::Reserved => "reserved",
+            AddressKind::Global => "global",
+        };
+        f.write_str(s)
+    }
+}
+
+/* ---------------------------------- */
+
+/// IPv4 special-purpose blocks. Anything not listed is [AddressKind::Global].
+#[rustfmt::skip]
+const V4_SPECIAL: [([u8; 4], u8, AddressKind); 17] = [
+    ([0, 0, 0, 0], 32,          AddressKind::Unspecified),
+    ([0, 0, 0, 0], 8,           AddressKind::ThisNetwork),
+    ([10, 0, 0, 0], 8,          AddressKind::Private),
+    ([100, 64, 0, 0], 10,       AddressKind::SharedAddress),
+    ([127, 0, 0, 0], 8,         AddressKind::Loopback),
+    ([169, 254, 0, 0], 16,      AddressKind::LinkLocal),
+    ([172, 16, 0, 0], 12,       AddressKind::Private),
+    ([192, 0, 0, 0], 24,        AddressKind::ProtocolAssignment),
+    ([192, 0, 2, 0], 24,        AddressKind::Documentation),
+    ([192, 88, 99, 0], 24,      AddressKind::Reserved),     // deprecated 6to4 relay anycast
+    ([192, 168, 0, 0], 16,      AddressKind::Private),
+    ([198, 18, 0, 0], 15,       AddressKind::Benchmarking),
+    ([198, 51, 100, 0], 24,     AddressKind::Documentation),
+    ([203, 0, 113, 0], 24,      AddressKind::Documentation),
+    ([224, 0, 0, 0], 4,         AddressKind::Multicast),
+    ([240, 0, 0, 0], 4,         AddressKind::Reserved),
+    ([255, 255, 255, 255], 32,  AddressKind::Broadcast),
+];
+
+/// IPv6 global unicast space. Anything outside it and not listed below is [AddressKind::Reserved].
+pub(crate) const V6_GLOBAL_UNICAST: ([u16; 8], u8) = ([0x2000, 0, 0, 0, 0, 0, 0, 0], 3);
+
+/// IPv6 special-purpose blocks, both inside and outside the global unicast space.
+#[rustfmt::skip]
+const V6_SPECIAL: [([u16; 8], u8, AddressKind); 12] = [
+    ([0, 0, 0, 0, 0, 0, 0, 0], 128,                 AddressKind::Unspecified),
+    ([0, 0, 0, 0, 0, 0, 0, 1], 128,                 AddressKind::Loopback),
+    ([0, 0, 0, 0, 0, 0xffff, 0, 0], 96,             AddressKind::Ipv4Mapped),
+    ([0x64, 0xff9b, 0, 0, 0, 0, 0, 0], 96,          AddressKind::Translation),
+    ([0x64, 0xff9b, 1, 0, 0, 0, 0, 0], 48,          AddressKind::Translation),
+    ([0x100, 0, 0, 0, 0, 0, 0, 0], 64,              AddressKind::Discard),
+    ([0x2001, 0, 0, 0, 0, 0, 0, 0], 23,             AddressKind::ProtocolAssignment),
+    ([0x2001, 0x0db8, 0, 0, 0, 0, 0, 0], 32,        AddressKind::Documentation),
+    ([0x3fff, 0, 0, 0, 0, 0, 0, 0], 20,             AddressKind::Documentation),
+    ([0xfc00, 0, 0, 0, 0, 0, 0, 0], 7,              AddressKind::UniqueLocal),
+    ([0xfe80, 0, 0, 0, 0, 0, 0, 0], 10,             AddressKind::LinkLocal),
+    ([0xff00, 0, 0, 0, 0, 0, 0, 0], 8,              AddressKind::Multicast),
+];
+
+/**
+Classify an address against the IANA special-purpose address registries.
+
+The most specific matching block wins, f.ex. `0.0.0.0` is
+[AddressKind::Unspecified] rather than [AddressKind::ThisNetwork].
+*/
+pub fn classify_ip(ip: IpAddr) -> AddressKind {
+    let host: Range = cidr_to_range(ip_to_host_cidr(ip));
+    let fallback: AddressKind = match host.fam {
+        IpFam::V4 => AddressKind::Global,
+        IpFam::V6 if v6_range(V6_GLOBAL_UNICAST.0, V6_GLOBAL_UNICAST.1).contains(&host) => {
+            AddressKind::Global
+        }
+        IpFam::V6 => AddressKind::Reserved,
+    };
+    special_blocks(host.fam)
+        .filter(|(r, _, _)| r.contains(&host))
+        .max_by_key(|(_, p, _)| *p)
+        .map_or(fallback, |(_, _, k)| k)
+}
+
+/// The special-purpose blocks of a family as ranges, with their prefix and kind.
+pub(crate) fn special_blocks(fam: IpFam) -> impl Iterator<Item = (Range, u8, AddressKind)> {
+    let v4 = V4_SPECIAL
+        .iter()
+        .filter(move |_| fam == IpFam::V4)
+        .map(|(o, p, k)| (v4_range(*o, *p), *p, *k));
+    let v6 = V6_SPECIAL
+        .iter()
+        .filter(move |_| fam == IpFam::V6)
+        .map(|(s, p, k)| (v6_range(*s, *p), *p, *k));
+    v4.chain(v6)
+}
+
+#[inline]
+pub(crate) fn v4_range(octets: [u8; 4], prefix: u8) -> Range {
+    cidr_to_range(Cidr {
+        addr: IpAddr::V4(Ipv4Addr::from(octets)),
+        prefix,
+        zone: None,
+    })
+}
+
+#[inline]
+pub(crate) fn v6_range(segments: [u16; 8], prefix: u8) -> Range {
+    cidr_to_range(Cidr {
+        addr: IpAddr::V6(Ipv6Addr::from(segments)),
+        prefix,
+        zone: None,
+    })
+}
+
+/* -------------------------------------------------------------------------- */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rustfmt::skip]
+    const CASES: [(&str, AddressKind); 16] = [
+        ("0.0.0.0",             AddressKind::Unspecified),
+        ("0.1.2.3",             AddressKind::ThisNetwork),
+        ("10.20.30.40",         AddressKind::Private),
+        ("100.100.0.1",         AddressKind::SharedAddress),
+        ("127.0.0.53",          AddressKind::Loopback),
+        ("198.51.100.7",        AddressKind::Documentation),
+        ("239.255.255.250",     AddressKind::Multicast),
+        ("255.255.255.255",     AddressKind::Broadcast),
+        ("8.8.8.8",             AddressKind::Global),
+        ("::",                  AddressKind::Unspecified),
+        ("::1",                 AddressKind::Loopback),
+        ("::ffff:10.0.0.1",     AddressKind::Ipv4Mapped),
+        ("2001:db8::1",         AddressKind::Documentation),
+        ("fd00::1",             AddressKind::UniqueLocal),
+        ("2606:4700::1111",     AddressKind::Global),
+        ("4000::1",             AddressKind::Reserved),
+    ];
+
+    #[test]
+    fn test_classify_ip() {
+        for (ip, kind) in CASES {
+            assert_eq!(classify_ip(ip.parse().unwrap()), kind, "{ip}");
+        }
+    }
+}
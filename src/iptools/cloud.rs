@@ -0,0 +1,385 @@
+// Copyright (c) 2026 Mikko Tanner. All rights reserved.
+// Licensed under the MIT License or the Apache License, Version 2.0.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+/*!
+Parsers for the IP range documents published by cloud providers, for
+building allow- and blocklists from locally saved copies:
+- AWS `ip-ranges.json`
+- GCP `cloud.json`
+- Azure service tags (`ServiceTags_Public_*.json`)
+- Cloudflare `ips-v4` / `ips-v6` text lists
+*/
+
+use super::{strings::*, structs::Cidr, AddressError};
+use serde::{Deserialize, Serialize};
+use std::{error, fmt, fs, io, path::Path};
+
+#[derive(Debug)]
+pub enum CloudError {
+    /// reading the document failed
+    Io(io::Error),
+    /// the document is not valid JSON of the expected shape
+    Json(serde_json::Error),
+    /// a prefix in the document is not a valid CIDR
+    Prefix {
+        prefix: String,
+        source: AddressError,
+    },
+}
+
+impl fmt::Display for CloudError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CloudError::Io(_) => f.write_str(ERR_CLOUD_IO),
+            CloudError::Json(_) => f.write_str(ERR_CLOUD_JSON),
+            CloudError::Prefix { prefix, .. } => write!(f, "{ERR_CLOUD_PREFIX}: '{prefix}'"),
+        }
+    }
+}
+
+impl error::Error for CloudError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            CloudError::Io(e) => Some(e),
+            CloudError::Json(e) => Some(e),
+            CloudError::Prefix { source, .. } => Some(source),
+        }
+    }
+}
+
+impl From<io::Error> for CloudError {
+    fn from(e: io::Error) -> Self {
+        CloudError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for CloudError {
+    fn from(e: serde_json::Error) -> Self {
+        CloudError::Json(e)
+    }
+}
+
+/* ---------------------------------- */
+
+/// Publisher of a range document, selecting its format.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum CloudProvider {
+    Aws,
+    Gcp,
+    Azure,
+    Cloudflare,
+}
+
+impl CloudProvider {
+    /// Parse a document in this provider's format.
+    pub fn parse(&self, doc: &str) -> Result<Vec<CloudRange>, CloudError> {
+        match self {
+            CloudProvider::Aws => parse_aws(doc),
+            CloudProvider::Gcp => parse_gcp(doc),
+            CloudProvider::Azure => parse_azure(doc),
+            CloudProvider::Cloudflare => parse_cloudflare(doc),
+        }
+    }
+
+    /// Read and parse a saved document in this provider's format.
+    pub fn load(&self, path: impl AsRef<Path>) -> Result<Vec<CloudRange>, CloudError> {
+        self.parse(&fs::read_to_string(path)?)
+    }
+}
+
+impl fmt::Display for CloudProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CloudProvider::Aws => "aws",
+            CloudProvider::Gcp => "gcp",
+            CloudProvider::Azure => "azure",
+            CloudProvider::Cloudflare => "cloudflare",
+        })
+    }
+}
+
+/// A published prefix with its metadata, as given in the document.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CloudRange {
+    pub cidr: Cidr,
+    pub provider: CloudProvider,
+    /// f.ex. `eu-west-1` (AWS), `europe-west1` (GCP) or `westeurope` (Azure)
+    pub region: Option<String>,
+    /// f.ex. `S3` (AWS), `Google Cloud` (GCP) or `AzureStorage` (Azure)
+    pub service: Option<String>,
+}
+
+impl CloudRange {
+    /// `provider/service/region`, leaving out missing parts, f.ex. as a label for [collapse_tagged](super::collapse_tagged).
+    pub fn label(&self) -> String {
+        let mut parts: Vec<String> = vec![self.provider.to_string()];
+        parts.extend(self.service.iter().chain(self.region.iter()).cloned());
+        parts.join("/")
+    }
+}
+
+/**
+Select [CloudRange]s by service and region, compared case-insensitively.
+Each `with_*` call adds an accepted value; no values accepts anything.
+*/
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CloudFilter {
+    services: Vec<String>,
+    regions: Vec<String>,
+}
+
+impl CloudFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_service(mut self, service: impl Into<String>) -> Self {
+        self.services.push(service.into());
+        self
+    }
+
+    pub fn with_region(mut self, region: impl Into<String>) -> Self {
+        self.regions.push(region.into());
+        self
+    }
+
+    pub fn matches(&self, range: &CloudRange) -> bool {
+        let accepts = |wanted: &[String], value: &Option<String>| {
+            wanted.is_empty()
+                || value
+                    .as_ref()
+                    .is_some_and(|v| wanted.iter().any(|w| w.eq_ignore_ascii_case(v)))
+        };
+        accepts(&self.services, &range.service) && accepts(&self.regions, &range.region)
+    }
+
+    /// The CIDRs of the matching ranges, ready for [collapse_cidrs](super::collapse_cidrs).
+    pub fn cidrs(&self, ranges: &[CloudRange]) -> Vec<Cidr> {
+        ranges
+            .iter()
+            .filter(|r| self.matches(r))
+            .map(|r| r.cidr)
+            .collect()
+    }
+}
+
+/* ---------------------------------- */
+
+#[derive(Deserialize)]
+struct AwsDoc {
+    #[serde(default)]
+    prefixes: Vec<AwsPrefix>,
+    #[serde(default)]
+    ipv6_prefixes: Vec<AwsPrefix>,
+}
+
+#[derive(Deserialize)]
+struct AwsPrefix {
+    #[serde(alias = "ipv6_prefix")]
+    ip_prefix: String,
+    region: Option<String>,
+    service: Option<String>,
+}
+
+/// Parse an AWS `ip-ranges.json`. Prefixes are listed once per service, so `AMAZON` overlaps the others.
+pub fn parse_aws(doc: &str) -> Result<Vec<CloudRange>, CloudError> {
+    let doc: AwsDoc = serde_json::from_str(doc)?;
+    doc.prefixes
+        .into_iter()
+        .chain(doc.ipv6_prefixes)
+        .map(|p| cloud_range(CloudProvider::Aws, &p.ip_prefix, p.region, p.service))
+        .collect()
+}
+
+#[derive(Deserialize)]
+struct GcpDoc {
+    #[serde(default)]
+    prefixes: Vec<GcpPrefix>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GcpPrefix {
+    #[serde(alias = "ipv6Prefix")]
+    ipv4_prefix: String,
+    scope: Option<String>,
+    service: Option<String>,
+}
+
+/// Parse a GCP `cloud.json`. The `scope` of a prefix is its region.
+pub fn parse_gcp(doc: &str) -> Result<Vec<CloudRange>, CloudError> {
+    let doc: GcpDoc = serde_json::from_str(doc)?;
+    doc.prefixes
+        .into_iter()
+        .map(|p| cloud_range(CloudProvider::Gcp, &p.ipv4_prefix, p.scope, p.service))
+        .collect()
+}
+
+#[derive(Deserialize)]
+struct AzureDoc {
+    #[serde(default)]
+    values: Vec<AzureTag>,
+}
+
+#[derive(Deserialize)]
+struct AzureTag {
+    /// `Service` or `Service.Region`
+    name: String,
+    properties: AzureProperties,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AzureProperties {
+    #[serde(default)]
+    region: String,
+    #[serde(default)]
+    system_service: String,
+    #[serde(default)]
+    address_prefixes: Vec<String>,
+}
+
+/**
+Parse an Azure service tags document. The service is the tag's
+`systemService`, or the name before the region if that is empty (f.ex.
+`AzureCloud` in `AzureCloud.westeurope`).
+*/
+pub fn parse_azure(doc: &str) -> Result<Vec<CloudRange>, CloudError> {
+    let doc: AzureDoc = serde_json::from_str(doc)?;
+    let mut out: Vec<CloudRange> = Vec::new();
+    for tag in doc.values {
+        let props: AzureProperties = tag.properties;
+        let service: String = match props.system_service.is_empty() {
+            true => tag.name.split('.').next().unwrap_or_default().to_string(),
+            false => props.system_service,
+        };
+        let region: Option<String> = Some(props.region).filter(|r| !r.is_empty());
+        for prefix in &props.address_prefixes {
+            out.push(cloud_range(
+                CloudProvider::Azure,
+                prefix,
+                region.clone(),
+                Some(service.clone()),
+            )?);
+        }
+    }
+    Ok(out)
+}
+
+/// Parse a Cloudflare text list, one CIDR per line. Blank lines and `#` comments are skipped.
+pub fn parse_cloudflare(doc: &str) -> Result<Vec<CloudRange>, CloudError> {
+    doc.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(|l| cloud_range(CloudProvider::Cloudflare, l, None, None))
+        .collect()
+}
+
+fn cloud_range(
+    provider: CloudProvider,
+    prefix: &str,
+    region: Option<String>,
+    service: Option<String>,
+) -> Result<CloudRange, CloudError> {
+    Ok(CloudRange {
+        cidr: prefix.parse().map_err(|source| CloudError::Prefix {
+            prefix: prefix.to_string(),
+            source,
+        })?,
+        provider,
+        region,
+        service,
+    })
+}
+
+/* -------------------------------------------------------------------------- */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::iptools::collapse_cidrs;
+
+    const AWS: &str = r#"{
+        "syncToken": "1700000000",
+        "createDate": "2026-01-01-00-00-00",
+        "prefixes": [
+            {"ip_prefix": "3.5.140.0/22", "region": "ap-northeast-2", "service": "AMAZON", "network_border_group": "ap-northeast-2"},
+            {"ip_prefix": "3.5.140.0/23", "region": "ap-northeast-2", "service": "S3", "network_border_group": "ap-northeast-2"},
+            {"ip_prefix": "52.95.150.0/24", "region": "eu-west-1", "service": "S3", "network_border_group": "eu-west-1"}
+        ],
+        "ipv6_prefixes": [
+            {"ipv6_prefix": "2a05:d07a:a000::/40", "region": "eu-west-1", "service": "S3", "network_border_group": "eu-west-1"}
+        ]
+    }"#;
+
+    const GCP: &str = r#"{
+        "syncToken": "1700000000",
+        "creationTime": "2026-01-01T00:00:00",
+        "prefixes": [
+            {"ipv4Prefix": "34.1.208.0/20", "service": "Google Cloud", "scope": "africa-south1"},
+            {"ipv6Prefix": "2600:1900:8000::/44", "service": "Google Cloud", "scope": "us-central1"}
+        ]
+    }"#;
+
+    const AZURE: &str = r#"{
+        "changeNumber": 300,
+        "cloud": "Public",
+        "values": [
+            {"name": "AzureCloud.westeurope", "id": "AzureCloud.westeurope", "properties": {
+                "changeNumber": 50, "region": "westeurope", "regionId": 18, "platform": "Azure",
+                "systemService": "", "addressPrefixes": ["13.69.0.0/17", "2603:1020:200::/46"]}},
+            {"name": "Storage", "id": "Storage", "properties": {
+                "changeNumber": 70, "region": "", "regionId": 0, "platform": "Azure",
+                "systemService": "AzureStorage", "addressPrefixes": ["13.65.24.0/24"]}}
+        ]
+    }"#;
+
+    const CLOUDFLARE: &str = "173.245.48.0/20\n103.21.244.0/22\n\n# v6\n2400:cb00::/32\n";
+
+    #[test]
+    fn test_parse_documents() {
+        let aws: Vec<CloudRange> = CloudProvider::Aws.parse(AWS).unwrap();
+        assert_eq!(aws.len(), 4);
+        assert_eq!(aws[3].cidr.to_string(), "2a05:d07a:a000::/40");
+        assert_eq!(aws[1].label(), "aws/S3/ap-northeast-2");
+
+        let gcp: Vec<CloudRange> = CloudProvider::Gcp.parse(GCP).unwrap();
+        assert_eq!(gcp[1].region.as_deref(), Some("us-central1"));
+        assert!(gcp[1].cidr.is_ipv6());
+
+        let azure: Vec<CloudRange> = CloudProvider::Azure.parse(AZURE).unwrap();
+        assert_eq!(azure.len(), 3);
+        assert_eq!(azure[0].label(), "azure/AzureCloud/westeurope");
+        assert_eq!(azure[2].label(), "azure/AzureStorage");
+
+        let cf: Vec<CloudRange> = CloudProvider::Cloudflare.parse(CLOUDFLARE).unwrap();
+        assert_eq!(cf.len(), 3);
+        assert_eq!(cf[2].label(), "cloudflare");
+
+        assert!(matches!(parse_aws("{"), Err(CloudError::Json(_))));
+        let err: CloudError = parse_cloudflare("10.0.0.0/8\nbogus").unwrap_err();
+        assert!(matches!(&err, CloudError::Prefix { prefix, .. } if prefix == "bogus"));
+        // the address error is chained, not repeated in the message
+        assert_eq!(err.to_string(), format!("{ERR_CLOUD_PREFIX}: 'bogus'"));
+        assert!(error::Error::source(&err).unwrap().is::<AddressError>());
+    }
+
+    #[test]
+    fn test_filter() {
+        let aws: Vec<CloudRange> = parse_aws(AWS).unwrap();
+        let s3: Vec<Cidr> = CloudFilter::new().with_service("s3").cidrs(&aws);
+        assert_eq!(s3.len(), 3);
+        let eu: Vec<Cidr> = CloudFilter::new()
+            .with_service("S3")
+            .with_region("eu-west-1")
+            .with_region("eu-central-1")
+            .cidrs(&aws);
+        assert_eq!(eu.len(), 2);
+        // AMAZON covers the S3 /23
+        assert_eq!(collapse_cidrs(&CloudFilter::new().cidrs(&aws), 0).len(), 3);
+        // no region in the document never matches a region filter
+        let azure: Vec<CloudRange> = parse_azure(AZURE).unwrap();
+        assert!(CloudFilter::new().with_region("").cidrs(&azure).is_empty());
+    }
+}
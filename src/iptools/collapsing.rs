@@ -3,11 +3,16 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use super::{
-    strings::*,
+    prelude::*,
+    spec::AddressSpec,
     structs::{Cidr, IpFam, IpRange, Range},
     AddressError, IPV4_BITS, IPV6_BITS,
 };
-use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use alloc::collections::BinaryHeap;
+use core::{
+    cmp::Reverse,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+};
 
 /**
 Collapse a list of CIDRs into an equivalent, minimal set of CIDRs.
@@ -54,23 +59,35 @@ pub fn collapse_ips(input: &[IpAddr], max_gap: u128) -> Vec<Cidr> {
 }
 
 /**
-Collapse a list of strings (CIDRs or IPs) into an equivalent, minimal set of CIDRs.
+Collapse a list of strings (IPs, CIDRs or ranges, see [AddressSpec]) into
+an equivalent, minimal set of CIDRs. Invalid entries are skipped.
 
 If `max_gap` > 0, nearby ranges separated by <= `max_gap` IPs will be
 fuzzily merged as well (over-approximation).
 */
 pub fn collapse_strings(input: &[impl AsRef<str>], max_gap: u128) -> Vec<Cidr> {
-    let mut cidrs: Vec<Cidr> = Vec::with_capacity(input.len());
-    for s in input {
-        if s.as_ref().contains(SLASH) {
-            if let Ok(cidr) = s.as_ref().parse::<Cidr>() {
-                cidrs.push(cidr);
-            }
-        } else if let Ok(ip) = s.as_ref().parse::<IpAddr>() {
-            cidrs.push(ip_to_host_cidr(ip));
-        }
-    }
-    collapse_cidrs(&cidrs, max_gap)
+    let mut ranges: Vec<Range> = parse_ranges(input);
+    ranges.sort_by_key(Range::cmp_key);
+    merge_to_cidrs(&ranges, max_gap)
+}
+
+/**
+Collapse a list of [AddressSpec]s into an equivalent, minimal set of CIDRs.
+
+If `max_gap` > 0, nearby ranges separated by <= `max_gap` IPs will be
+fuzzily merged as well (over-approximation).
+
+## Errors
+[AddressError::Mismatch] for a range spec (converted from an [IpRange])
+whose ends are of different IP families.
+*/
+pub fn collapse_specs(input: &[AddressSpec], max_gap: u128) -> Result<Vec<Cidr>, AddressError> {
+    let mut ranges: Vec<Range> = input
+        .iter()
+        .map(AddressSpec::range)
+        .collect::<Result<Vec<Range>, AddressError>>()?;
+    ranges.sort_by_key(Range::cmp_key);
+    Ok(merge_to_cidrs(&ranges, max_gap))
 }
 
 /// Convert a single IP (host) to an equivalent CIDR (/32 or /128).
@@ -79,10 +96,12 @@ pub fn ip_to_host_cidr(ip: IpAddr) -> Cidr {
         IpAddr::V4(_) => Cidr {
             addr: ip,
             prefix: IPV4_BITS,
+            zone: None,
         },
         IpAddr::V6(_) => Cidr {
             addr: ip,
             prefix: IPV6_BITS,
+            zone: None,
         },
     }
 }
@@ -154,6 +173,277 @@ pub fn collapse_ranges_tuples(input: &[(IpAddr, IpAddr)]) -> Result<Vec<Cidr>, A
     collapse_ranges(&v)
 }
 
+/**
+Collapse a list of CIDRs into an equivalent, minimal set of inclusive IP
+ranges. Same as [collapse_cidrs], but stops before the CIDR decomposition:
+f.ex. `10.0.0.1-10.0.0.6` is one range, but four CIDRs.
+
+If `max_gap` > 0, nearby ranges separated by <= `max_gap` IPs will be
+fuzzily merged as well (over-approximation).
+*/
+pub fn collapse_cidrs_to_ranges(input: &[Cidr], max_gap: u128) -> Vec<IpRange> {
+    let mut ranges: Vec<Range> = input.iter().map(|c| cidr_to_range(*c)).collect();
+    ranges.sort_by_key(Range::cmp_key);
+    merge_to_ipranges(&ranges, max_gap)
+}
+
+/// Collapse a list of IPs into a minimal set of inclusive IP ranges, see [collapse_cidrs_to_ranges].
+pub fn collapse_ips_to_ranges(input: &[IpAddr], max_gap: u128) -> Vec<IpRange> {
+    let cidrs: Vec<Cidr> = input.iter().map(|&ip| ip_to_host_cidr(ip)).collect();
+    collapse_cidrs_to_ranges(&cidrs, max_gap)
+}
+
+/**
+Collapse a list of strings into a minimal set of inclusive IP ranges, see
+[collapse_cidrs_to_ranges]. Accepts IPs, CIDRs and ranges; invalid entries
+are skipped.
+*/
+pub fn collapse_strings_to_ranges(input: &[impl AsRef<str>], max_gap: u128) -> Vec<IpRange> {
+    let mut ranges: Vec<Range> = parse_ranges(input);
+    ranges.sort_by_key(Range::cmp_key);
+    merge_to_ipranges(&ranges, max_gap)
+}
+
+/**
+Collapse a list of [AddressSpec]s into a minimal set of inclusive IP ranges,
+see [collapse_cidrs_to_ranges]. Fails like [collapse_specs].
+*/
+pub fn collapse_specs_to_ranges(
+    input: &[AddressSpec],
+    max_gap: u128,
+) -> Result<Vec<IpRange>, AddressError> {
+    let mut ranges: Vec<Range> = input
+        .iter()
+        .map(AddressSpec::range)
+        .collect::<Result<Vec<Range>, AddressError>>()?;
+    ranges.sort_by_key(Range::cmp_key);
+    Ok(merge_to_ipranges(&ranges, max_gap))
+}
+
+/// Collapse a list of inclusive IP ranges into a minimal set of them, see [collapse_cidrs_to_ranges].
+pub fn collapse_ranges_to_ranges(
+    input: &[IpRange],
+    max_gap: u128,
+) -> Result<Vec<IpRange>, AddressError> {
+    let mut ranges: Vec<Range> = input
+        .iter()
+        .map(|r| iprange_to_range(*r))
+        .collect::<Result<Vec<Range>, AddressError>>()?;
+    ranges.sort_by_key(Range::cmp_key);
+    Ok(merge_to_ipranges(&ranges, max_gap))
+}
+
+/// Parse strings as [AddressSpec]s into unsorted ranges, skipping invalid ones.
+fn parse_ranges(input: &[impl AsRef<str>]) -> Vec<Range> {
+    input
+        .iter()
+        .filter_map(|s| s.as_ref().parse::<AddressSpec>().ok()?.range().ok())
+        .collect()
+}
+
+/// Merge sorted ranges (optionally fuzzily) and convert them to minimal CIDRs.
+fn merge_to_cidrs(sorted: &[Range], max_gap: u128) -> Vec<Cidr> {
+    let mut merged: Vec<Range> = merge_ranges(sorted);
+    if max_gap > 0 {
+        merged = merge_ranges_fuzzy(&merged, max_gap);
+    }
+    merged.into_iter().flat_map(range_to_cidrs).collect()
+}
+
+/// Merge sorted ranges (optionally fuzzily) and convert them to [IpRange]s.
+fn merge_to_ipranges(sorted: &[Range], max_gap: u128) -> Vec<IpRange> {
+    let mut merged: Vec<Range> = merge_ranges(sorted);
+    if max_gap > 0 {
+        merged = merge_ranges_fuzzy(&merged, max_gap);
+    }
+    merged.into_iter().map(range_to_iprange).collect()
+}
+
+/**
+Drop the IPv6 zones of `input`, so that collapsing it merges networks
+regardless of zone. Collapsing otherwise only merges within a zone.
+*/
+pub fn strip_zones(input: &[Cidr]) -> Vec<Cidr> {
+    input.iter().map(|c| c.without_zone()).collect()
+}
+
+/**
+Remove the addresses in `exclude` from `input`, returning the minimal set of
+CIDRs covering what remains. Both inputs may overlap and be unsorted.
+*/
+pub fn exclude_cidrs(input: &[Cidr], exclude: &[Cidr]) -> Vec<Cidr> {
+    let base: Vec<Range> = sorted_merged(input);
+    let remove: Vec<Range> = sorted_merged(exclude);
+    subtract_ranges(&base, &remove)
+        .into_iter()
+        .flat_map(range_to_cidrs)
+        .collect()
+}
+
+/**
+Collapse a list of CIDRs into at most `budget` CIDRs, f.ex. to fit a
+firewall or route table with a hard entry limit.
+
+The exact collapse is tried first. If it does not fit, neighbouring blocks
+of the same family are repeatedly replaced by their smallest common
+supernet, always picking the pair which adds the fewest extra addresses
+(a greedy over-approximation). The result never has fewer than one block
+per IP family present in the input, even if `budget` is smaller.
+*/
+pub fn collapse_cidrs_budget(input: &[Cidr], budget: usize) -> Vec<Cidr> {
+    let exact: Vec<Cidr> = collapse_cidrs(input, 0);
+    if exact.len() <= budget {
+        return exact;
+    }
+
+    // doubly linked list over the blocks, with a lazily invalidated heap of
+    // merge candidates keyed by the number of addresses a merge would add
+    let mut nodes: Vec<BudgetNode> = exact
+        .iter()
+        .enumerate()
+        .map(|(i, c)| BudgetNode {
+            range: cidr_to_range(*c),
+            prefix: c.prefix,
+            covered: cidr_to_range(*c).len(),
+            prev: i.checked_sub(1),
+            next: Some(i + 1).filter(|n| *n < exact.len()),
+            version: 0,
+            alive: true,
+        })
+        .collect();
+    let mut heap: BinaryHeap<Candidate> = BinaryHeap::new();
+    let push = |heap: &mut BinaryHeap<_>, nodes: &[BudgetNode], a: usize, b: usize| {
+        if let Some((_, cost)) = supernet_of(&nodes[a], &nodes[b]) {
+            heap.push(Reverse((cost, a, nodes[a].version, b, nodes[b].version)));
+        }
+    };
+    for i in 1..nodes.len() {
+        push(&mut heap, &nodes, i - 1, i);
+    }
+
+    let mut count: usize = nodes.len();
+    while count > budget {
+        let Some(Reverse((_, a, va, b, vb))) = heap.pop() else {
+            break; // only cross-family neighbours left
+        };
+        let stale: bool = !nodes[a].alive
+            || !nodes[b].alive
+            || nodes[a].version != va
+            || nodes[b].version != vb
+            || nodes[a].next != Some(b);
+        if stale {
+            continue;
+        }
+
+        let (sup, _) = supernet_of(&nodes[a], &nodes[b]).expect("same family");
+        nodes[a].covered = nodes[a].covered.saturating_add(nodes[b].covered);
+        nodes[a].range = cidr_to_range(sup);
+        nodes[a].prefix = sup.prefix;
+        nodes[a].version += 1;
+        unlink(&mut nodes, b);
+        count -= 1;
+
+        // the supernet may have swallowed further neighbours on either side
+        while let Some(n) = nodes[a]
+            .next
+            .filter(|n| nodes[a].range.contains(&nodes[*n].range))
+        {
+            nodes[a].covered = nodes[a].covered.saturating_add(nodes[n].covered);
+            unlink(&mut nodes, n);
+            count -= 1;
+        }
+        while let Some(p) = nodes[a]
+            .prev
+            .filter(|p| nodes[a].range.contains(&nodes[*p].range))
+        {
+            nodes[a].covered = nodes[a].covered.saturating_add(nodes[p].covered);
+            unlink(&mut nodes, p);
+            count -= 1;
+        }
+
+        if let Some(p) = nodes[a].prev {
+            push(&mut heap, &nodes, p, a);
+        }
+        if let Some(n) = nodes[a].next {
+            push(&mut heap, &nodes, a, n);
+        }
+    }
+
+    // merged supernets may now be adjacent siblings, which collapse further
+    let left: Vec<Cidr> = nodes
+        .iter()
+        .filter(|n| n.alive)
+        .map(|n| Cidr {
+            addr: int_to_ip(n.range.fam, n.range.beg),
+            prefix: n.prefix,
+            zone: n.range.zone,
+        })
+        .collect();
+    collapse_cidrs(&left, 0)
+}
+
+/* ---------------------------------- */
+
+/// Merge candidate: (added addresses, left node, its version, right node, its version).
+type Candidate = Reverse<(u128, usize, u32, usize, u32)>;
+
+/// A block in [collapse_cidrs_budget]'s working list.
+struct BudgetNode {
+    range: Range,
+    prefix: u8,
+    /// addresses of the original input inside `range`
+    covered: u128,
+    prev: Option<usize>,
+    next: Option<usize>,
+    version: u32,
+    alive: bool,
+}
+
+/// Smallest common supernet of two blocks and the number of addresses it adds.
+fn supernet_of(a: &BudgetNode, b: &BudgetNode) -> Option<(Cidr, u128)> {
+    if a.range.space() != b.range.space() {
+        return None;
+    }
+    let (bits, shared): (u8, u32) = match a.range.fam {
+        IpFam::V4 => (
+            IPV4_BITS,
+            ((a.range.beg ^ b.range.beg) as u32).leading_zeros(),
+        ),
+        IpFam::V6 => (IPV6_BITS, (a.range.beg ^ b.range.beg).leading_zeros()),
+    };
+    let prefix: u8 = (shared.min(bits as u32) as u8).min(a.prefix).min(b.prefix);
+    let beg: u128 = a.range.beg & mask_u128(bits, prefix);
+    let sup: Cidr = Cidr {
+        addr: int_to_ip(a.range.fam, beg),
+        prefix,
+        zone: a.range.zone,
+    };
+    let added: u128 = cidr_to_range(sup)
+        .len()
+        .saturating_sub(a.covered.saturating_add(b.covered));
+    Some((sup, added))
+}
+
+#[inline]
+fn unlink(nodes: &mut [BudgetNode], i: usize) {
+    let (prev, next) = (nodes[i].prev, nodes[i].next);
+    if let Some(p) = prev {
+        nodes[p].next = next;
+    }
+    if let Some(n) = next {
+        nodes[n].prev = prev;
+    }
+    nodes[i].alive = false;
+}
+
+/// Convert CIDRs into sorted, merged ranges.
+#[inline]
+pub(crate) fn sorted_merged(input: &[Cidr]) -> Vec<Range> {
+    let mut ranges: Vec<Range> = input.iter().map(|c| cidr_to_range(*c)).collect();
+    ranges.sort_by_key(Range::cmp_key);
+    merge_ranges(&ranges)
+}
+
 /* ---------------------------------- */
 
 /// Convert a CIDR to an inclusive range.
@@ -167,6 +457,7 @@ pub(crate) fn cidr_to_range(c: Cidr) -> Range {
             let end: u32 = net | !mask;
             Range {
                 fam: IpFam::V4,
+                zone: None,
                 beg: net as u128,
                 end: end as u128,
             }
@@ -179,6 +470,7 @@ pub(crate) fn cidr_to_range(c: Cidr) -> Range {
             let end: u128 = net | !mask;
             Range {
                 fam: IpFam::V6,
+                zone: c.zone,
                 beg: net,
                 end,
             }
@@ -186,13 +478,13 @@ pub(crate) fn cidr_to_range(c: Cidr) -> Range {
     }
 }
 
-/// Merge overlapping/adjacent ranges within each IP family. Input must be sorted.
+/// Merge overlapping/adjacent ranges within each IP family and zone. Input must be sorted.
 #[inline]
-fn merge_ranges(sorted: &[Range]) -> Vec<Range> {
+pub(crate) fn merge_ranges(sorted: &[Range]) -> Vec<Range> {
     let mut out: Vec<Range> = Vec::with_capacity(sorted.len());
     for r in sorted.iter().copied() {
         if let Some(last) = out.last_mut() {
-            if last.fam == r.fam {
+            if last.space() == r.space() {
                 // overlap or adjacency?
                 if r.beg <= last.end.saturating_add(1) {
                     if r.end > last.end {
@@ -213,11 +505,11 @@ Merge nearby ranges separated by <= `max_gap` IPs (fuzzy over-approximation).
 Input must be sorted and previously merged, or it'll be a GIGO situation.
 */
 #[inline]
-fn merge_ranges_fuzzy(merged: &[Range], max_gap: u128) -> Vec<Range> {
+pub(crate) fn merge_ranges_fuzzy(merged: &[Range], max_gap: u128) -> Vec<Range> {
     let mut out: Vec<Range> = Vec::with_capacity(merged.len());
     for r in merged.iter().copied() {
         if let Some(last) = out.last_mut() {
-            if last.fam == r.fam {
+            if last.space() == r.space() {
                 let gap: u128 = r.beg.saturating_sub(last.end.saturating_add(1));
                 if gap <= max_gap {
                     // swallow the gap by extending end
@@ -231,8 +523,83 @@ fn merge_ranges_fuzzy(merged: &[Range], max_gap: u128) -> Vec<Range> {
     out
 }
 
+/// Intersection of two sorted and merged range lists.
+pub(crate) fn intersect_ranges(a: &[Range], b: &[Range]) -> Vec<Range> {
+    let mut out: Vec<Range> = Vec::new();
+    let (mut i, mut j) = (0usize, 0usize);
+    while i < a.len() && j < b.len() {
+        let (x, y) = (a[i], b[j]);
+        if x.space() != y.space() {
+            // skip ahead in whichever list is still in the lower family/zone
+            if x.cmp_key() < y.cmp_key() {
+                i += 1;
+            } else {
+                j += 1;
+            }
+            continue;
+        }
+        let beg: u128 = x.beg.max(y.beg);
+        let end: u128 = x.end.min(y.end);
+        if beg <= end {
+            out.push(Range {
+                fam: x.fam,
+                zone: x.zone,
+                beg,
+                end,
+            });
+        }
+        if x.end < y.end {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    out
+}
+
+/// Difference `a - b` of two sorted and merged range lists.
+pub(crate) fn subtract_ranges(a: &[Range], b: &[Range]) -> Vec<Range> {
+    let mut out: Vec<Range> = Vec::with_capacity(a.len());
+    let mut j: usize = 0;
+    for x in a.iter().copied() {
+        // skip subtrahends which end before this range begins
+        while j < b.len() && (b[j].space(), b[j].end) < (x.space(), x.beg) {
+            j += 1;
+        }
+
+        let mut beg: u128 = x.beg;
+        let mut remains: bool = true;
+        let mut k: usize = j;
+        while k < b.len() && b[k].space() == x.space() && b[k].beg <= x.end {
+            if b[k].beg > beg {
+                out.push(Range {
+                    fam: x.fam,
+                    zone: x.zone,
+                    beg,
+                    end: b[k].beg - 1,
+                });
+            }
+            if b[k].end >= x.end {
+                remains = false;
+                break;
+            }
+            beg = beg.max(b[k].end + 1);
+            k += 1;
+        }
+        if remains {
+            out.push(Range {
+                fam: x.fam,
+                zone: x.zone,
+                beg,
+                end: x.end,
+            });
+        }
+    }
+    out
+}
+
 /// Decompose an inclusive range into the minimal set of CIDRs.
-fn range_to_cidrs(r: Range) -> Vec<Cidr> {
+pub(crate) fn range_to_cidrs(r: Range) -> Vec<Cidr> {
     let bits: u8 = match r.fam {
         IpFam::V4 => IPV4_BITS,
         IpFam::V6 => IPV6_BITS,
@@ -241,7 +608,7 @@ fn range_to_cidrs(r: Range) -> Vec<Cidr> {
     // Full address space special-case
     if bits == IPV6_BITS && r.beg == 0 && r.end == u128::MAX {
         #[rustfmt::skip]
-        return vec![Cidr { addr: IpAddr::V6(Ipv6Addr::UNSPECIFIED), prefix: 0 }];
+        return vec![Cidr { addr: IpAddr::V6(Ipv6Addr::UNSPECIFIED), prefix: 0, zone: r.zone }];
     }
 
     let mut start: u128 = r.beg;
@@ -267,6 +634,7 @@ fn range_to_cidrs(r: Range) -> Vec<Cidr> {
         out.push(Cidr {
             addr: int_to_ip(r.fam, start),
             prefix,
+            zone: r.zone,
         });
 
         // prefix==0 for v6 should have been caught by the full-space
@@ -279,14 +647,18 @@ fn range_to_cidrs(r: Range) -> Vec<Cidr> {
         // pow is <= 128; for v6, pow==128 would imply prefix==0, but we guard above
         let block_size_pow: u32 = (bits - prefix) as u32;
         let block_size: u128 = 1u128 << block_size_pow;
-        start = start.saturating_add(block_size);
+        match start.checked_add(block_size) {
+            Some(next) => start = next,
+            // block reached the top of the IPv6 space
+            None => break,
+        }
     }
 
     out
 }
 
 /// Convert an [IpRange] to a [Range].
-fn iprange_to_range(r: IpRange) -> Result<Range, AddressError> {
+pub(crate) fn iprange_to_range(r: IpRange) -> Result<Range, AddressError> {
     match (r.beg, r.end) {
         (IpAddr::V4(a), IpAddr::V4(b)) => {
             let aa: u32 = u32::from_be_bytes(a.octets());
@@ -294,6 +666,7 @@ fn iprange_to_range(r: IpRange) -> Result<Range, AddressError> {
             let (beg, end) = if aa <= bb { (aa, bb) } else { (bb, aa) };
             Ok(Range {
                 fam: IpFam::V4,
+                zone: None,
                 beg: beg as u128,
                 end: end as u128,
             })
@@ -304,6 +677,7 @@ fn iprange_to_range(r: IpRange) -> Result<Range, AddressError> {
             let (beg, end) = if aa <= bb { (aa, bb) } else { (bb, aa) };
             Ok(Range {
                 fam: IpFam::V6,
+                zone: r.zone,
                 beg,
                 end,
             })
@@ -312,6 +686,16 @@ fn iprange_to_range(r: IpRange) -> Result<Range, AddressError> {
     }
 }
 
+/// Convert a [Range] to an [IpRange].
+#[inline]
+pub(crate) fn range_to_iprange(r: Range) -> IpRange {
+    IpRange {
+        beg: int_to_ip(r.fam, r.beg),
+        end: int_to_ip(r.fam, r.end),
+        zone: r.zone,
+    }
+}
+
 /* ---------------------------------- */
 
 /**
@@ -391,10 +775,12 @@ mod tests {
             Cidr {
                 addr: IpAddr::V4(TST_A_1.parse().unwrap()),
                 prefix: 24,
+                zone: None,
             },
             Cidr {
                 addr: IpAddr::V4(TST_A_2.parse().unwrap()),
                 prefix: 24,
+                zone: None,
             },
         ];
         let out = collapse_cidrs(&input, 0);
@@ -408,10 +794,12 @@ mod tests {
             Cidr {
                 addr: IpAddr::V4(TST_B_1.parse().unwrap()),
                 prefix: 8,
+                zone: None,
             },
             Cidr {
                 addr: IpAddr::V4(TST_B_2.parse().unwrap()),
                 prefix: 24,
+                zone: None,
             },
         ];
         let out = collapse_cidrs(&input, 0);
@@ -425,10 +813,12 @@ mod tests {
             Cidr {
                 addr: IpAddr::V6(TST_C_1.parse().unwrap()),
                 prefix: 65,
+                zone: None,
             },
             Cidr {
                 addr: IpAddr::V6(TST_C_2.parse().unwrap()),
                 prefix: 65,
+                zone: None,
             },
         ];
         let out = collapse_cidrs(&input, 0);
@@ -440,6 +830,7 @@ mod tests {
     fn test_range_to_cidr() {
         let r = Range {
             fam: IpFam::V4,
+            zone: None,
             beg: 172u128 << 24 | 16u128 << 16 | 0u128 << 8 | 4u128,
             end: 172u128 << 24 | 16u128 << 16 | 0u128 << 8 | 7u128,
         };
@@ -523,4 +914,156 @@ mod tests {
         assert_eq!(out.len(), 1);
         assert_eq!(out[0].to_string(), RES_E_V6);
     }
+
+    #[test]
+    fn test_intersect_subtract() {
+        let r = |fam, beg, end| Range {
+            fam,
+            zone: None,
+            beg,
+            end,
+        };
+        let a: Vec<Range> = vec![
+            r(IpFam::V4, 0, 99),
+            r(IpFam::V4, 200, 299),
+            r(IpFam::V6, 0, 9),
+        ];
+        let b: Vec<Range> = vec![r(IpFam::V4, 50, 249), r(IpFam::V6, 5, u128::MAX)];
+        assert_eq!(
+            intersect_ranges(&a, &b),
+            vec![
+                r(IpFam::V4, 50, 99),
+                r(IpFam::V4, 200, 249),
+                r(IpFam::V6, 5, 9)
+            ]
+        );
+        assert_eq!(
+            subtract_ranges(&a, &b),
+            vec![
+                r(IpFam::V4, 0, 49),
+                r(IpFam::V4, 250, 299),
+                r(IpFam::V6, 0, 4)
+            ]
+        );
+        assert_eq!(
+            subtract_ranges(&b, &a),
+            vec![r(IpFam::V4, 100, 199), r(IpFam::V6, 10, u128::MAX)]
+        );
+    }
+
+    #[test]
+    fn test_exclude_cidrs() {
+        let base: Vec<Cidr> = vec![
+            "10.0.0.0/24".parse().unwrap(),
+            "2001:db8::/127".parse().unwrap(),
+        ];
+        let remove: Vec<Cidr> = vec![
+            "10.0.0.0/25".parse().unwrap(),
+            "2001:db8::1".parse().unwrap(),
+        ];
+        let out: Vec<String> = exclude_cidrs(&base, &remove)
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        assert_eq!(out, ["10.0.0.128/25", "2001:db8::/128"]);
+    }
+
+    #[test]
+    fn test_collapse_budget() {
+        let input: Vec<Cidr> = TST_E_V4
+            .iter()
+            .chain(&TST_E_V6)
+            .map(|s| s.parse().unwrap())
+            .collect();
+        let exact: Vec<Cidr> = collapse_cidrs(&input, 0);
+        assert_eq!(collapse_cidrs_budget(&input, exact.len()), exact);
+
+        let two: Vec<String> = collapse_cidrs_budget(&input, 2)
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        assert_eq!(two, [RES_E_V4, RES_E_V6]);
+        // never merges across families
+        assert_eq!(collapse_cidrs_budget(&input, 0).len(), 2);
+
+        // cheapest merge first (ties go to the lower address), never .11 + .13
+        let three: Vec<String> = collapse_cidrs_budget(&input[..4], 3)
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        assert_eq!(three, ["172.16.0.8/30", "172.16.0.13/32", "172.16.0.15/32"]);
+    }
+
+    #[test]
+    fn test_zones_merge_separately() {
+        let input: Vec<Cidr> = [
+            "fe80::/65%eth0",
+            "fe80::8000:0:0:0/65%eth0",
+            "fe80::/64%eth1",
+        ]
+        .iter()
+        .map(|s| s.parse().unwrap())
+        .collect();
+        let out: Vec<String> = collapse_cidrs(&input, 0)
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        assert_eq!(out, ["fe80::%eth0/64", "fe80::%eth1/64"]);
+
+        let out: Vec<Cidr> = collapse_cidrs(&strip_zones(&input), 0);
+        assert_eq!(out, ["fe80::/64".parse::<Cidr>().unwrap()]);
+    }
+
+    #[test]
+    fn test_collapse_to_ranges() {
+        let input: [&str; 5] = [
+            "10.0.0.1",
+            "10.0.0.2/31",
+            "10.0.0.4-10.0.0.6",
+            "10.0.0.9",
+            "2001:db8::/127",
+        ];
+        let out: Vec<String> = collapse_strings_to_ranges(&input, 0)
+            .iter()
+            .map(|r| r.to_string())
+            .collect();
+        assert_eq!(
+            out,
+            [
+                "10.0.0.1-10.0.0.6",
+                "10.0.0.9-10.0.0.9",
+                "2001:db8::-2001:db8::1"
+            ]
+        );
+
+        let fuzzy: Vec<IpRange> = collapse_strings_to_ranges(&input, 2);
+        assert_eq!(fuzzy[0].to_string(), "10.0.0.1-10.0.0.9");
+
+        assert_eq!(
+            collapse_cidrs_to_ranges(&collapse_strings(&input, 0), 0),
+            collapse_strings_to_ranges(&input, 0)
+        );
+    }
+
+    #[test]
+    fn test_collapse_specs_mixed_range() {
+        let mixed: IpRange = IpRange {
+            beg: "10.0.0.1".parse().unwrap(),
+            end: "::1".parse().unwrap(),
+            zone: None,
+        };
+        let specs: [AddressSpec; 2] = ["10.0.0.0/24".parse().unwrap(), AddressSpec::from(mixed)];
+        assert!(matches!(
+            collapse_specs(&specs, 0),
+            Err(AddressError::Mismatch(..))
+        ));
+        assert!(matches!(
+            collapse_specs_to_ranges(&specs, 0),
+            Err(AddressError::Mismatch(..))
+        ));
+        assert_eq!(
+            collapse_specs(&specs[..1], 0).unwrap(),
+            ["10.0.0.0/24".parse::<Cidr>().unwrap()]
+        );
+    }
 }
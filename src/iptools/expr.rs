@@ -0,0 +1,443 @@
+// Copyright (c) 2026 Mikko Tanner. All rights reserved.
+// Licensed under the MIT License or the Apache License, Version 2.0.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+/*!
+Set expressions over addresses, f.ex. for config files:
+
+```text
+10.0.0.0/8 - 10.1.0.0/16 + 192.168.1.5-20 & rfc1918
+```
+
+- `+` union, `-` difference, `&` intersection
+- all operators have the same precedence and are evaluated left to right,
+  so the above is `((10.0.0.0/8 - 10.1.0.0/16) + 192.168.1.5-20) & rfc1918`;
+  use parentheses for anything else
+- literals are IPs, CIDRs and ranges, as accepted by
+  [parse_ip_or_range](super::parse_ip_or_range), optionally with an IPv6 zone
+- names (`[A-Za-z_][A-Za-z0-9_-]*`) refer to sets given by the caller
+
+A `-` inside a literal is part of it (`10.0.0.1-9`), so the difference
+operator needs whitespace or a parenthesis on at least one side.
+*/
+
+use super::{
+    collapsing::{
+        intersect_ranges, iprange_to_range, merge_ranges, range_to_cidrs, sorted_merged,
+        subtract_ranges,
+    },
+    prelude::*,
+    strings::*,
+    structs::{Cidr, IpRange, Range},
+    AddressError,
+};
+use alloc::collections::BTreeMap;
+use core::{error, fmt, ops, str::FromStr};
+
+/// Characters which always end a literal or a name.
+const DELIMS: [char; 4] = ['+', '&', '(', ')'];
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ExprError {
+    /// a token where it does not belong, f.ex. two literals in a row
+    Unexpected {
+        span: ops::Range<usize>,
+        found: String,
+    },
+    /// the expression ended while an operand was expected
+    UnexpectedEnd { pos: usize },
+    /// `(` without a matching `)`
+    UnclosedParen { span: ops::Range<usize> },
+    /// a literal which is not an IP, CIDR or range; the parse error is the source
+    InvalidLiteral {
+        span: ops::Range<usize>,
+        literal: String,
+        source: AddressError,
+    },
+    /// a name missing from the caller's map
+    UnknownName {
+        span: ops::Range<usize>,
+        name: String,
+    },
+}
+
+impl ExprError {
+    /// Byte offsets of the offending part of the expression.
+    pub fn span(&self) -> ops::Range<usize> {
+        match self {
+            ExprError::Unexpected { span, .. }
+            | ExprError::UnclosedParen { span }
+            | ExprError::InvalidLiteral { span, .. }
+            | ExprError::UnknownName { span, .. } => span.clone(),
+            ExprError::UnexpectedEnd { pos } => *pos..*pos,
+        }
+    }
+
+    /**
+    The error with `expr` and a marker line under the offending part:
+
+    ```text
+    10.0.0.0/8 - lan
+                 ^^^ unknown name 'lan'
+    ```
+    */
+    pub fn render(&self, expr: &str) -> String {
+        let span: ops::Range<usize> = self.span();
+        let pad: usize = expr[..span.start.min(expr.len())].chars().count();
+        let width: usize = expr.get(span).map_or(0, |s| s.chars().count()).max(1);
+        format!("{expr}\n{}{} {self}", " ".repeat(pad), "^".repeat(width))
+    }
+}
+
+impl fmt::Display for ExprError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExprError::Unexpected { found, .. } => write!(f, "{ERR_EXPR_UNEXPECTED} '{found}'"),
+            ExprError::UnexpectedEnd { .. } => f.write_str(ERR_EXPR_END),
+            ExprError::UnclosedParen { .. } => f.write_str(ERR_EXPR_PAREN),
+            ExprError::InvalidLiteral { literal, .. } => {
+                write!(f, "{ERR_EXPR_LITERAL} '{literal}'")
+            }
+            ExprError::UnknownName { name, .. } => write!(f, "{ERR_EXPR_NAME} '{name}'"),
+        }
+    }
+}
+
+impl error::Error for ExprError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ExprError::InvalidLiteral { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/* ---------------------------------- */
+
+/**
+A parsed set expression, see the [module docs](self) for the syntax.
+
+Literals are parsed up front; names are resolved by [Expr::eval], so one
+expression can be evaluated against different sets.
+*/
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Expr {
+    root: Node,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Node {
+    /// sorted, merged
+    Set(Vec<Range>),
+    Name(String, ops::Range<usize>),
+    Op(Op, Box<Node>, Box<Node>),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Op {
+    Union,
+    Difference,
+    Intersection,
+}
+
+impl Expr {
+    /// Evaluate to the minimal list of CIDRs, resolving names from `names`.
+    pub fn eval(&self, names: &BTreeMap<String, Vec<Cidr>>) -> Result<Vec<Cidr>, ExprError> {
+        Ok(eval_node(&self.root, names)?
+            .into_iter()
+            .flat_map(range_to_cidrs)
+            .collect())
+    }
+
+    /// The names referenced by the expression, sorted and deduplicated.
+    pub fn names(&self) -> Vec<&str> {
+        let mut out: Vec<&str> = Vec::new();
+        let mut stack: Vec<&Node> = vec![&self.root];
+        while let Some(node) = stack.pop() {
+            match node {
+                Node::Set(_) => {}
+                Node::Name(name, _) => out.push(name),
+                Node::Op(_, a, b) => stack.extend([a.as_ref(), b.as_ref()]),
+            }
+        }
+        out.sort_unstable();
+        out.dedup();
+        out
+    }
+}
+
+impl FromStr for Expr {
+    type Err = ExprError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser: Parser = Parser {
+            tokens: tokenize(s),
+            pos: 0,
+            len: s.len(),
+        };
+        let root: Node = parser.expr()?;
+        match parser.tokens.get(parser.pos) {
+            // only a stray ')' can stop the top level early
+            Some((tok, span)) => Err(ExprError::Unexpected {
+                span: span.clone(),
+                found: tok.to_string(),
+            }),
+            None => Ok(Self { root }),
+        }
+    }
+}
+
+/// Parse and evaluate `expr` in one go, see [Expr].
+pub fn eval_expr(expr: &str, names: &BTreeMap<String, Vec<Cidr>>) -> Result<Vec<Cidr>, ExprError> {
+    expr.parse::<Expr>()?.eval(names)
+}
+
+fn eval_node(node: &Node, names: &BTreeMap<String, Vec<Cidr>>) -> Result<Vec<Range>, ExprError> {
+    match node {
+        Node::Set(ranges) => Ok(ranges.clone()),
+        Node::Name(name, span) => match names.get(name) {
+            Some(cidrs) => Ok(sorted_merged(cidrs)),
+            None => Err(ExprError::UnknownName {
+                span: span.clone(),
+                name: name.clone(),
+            }),
+        },
+        Node::Op(op, a, b) => {
+            let (a, b): (Vec<Range>, Vec<Range>) = (eval_node(a, names)?, eval_node(b, names)?);
+            Ok(match op {
+                Op::Union => {
+                    let mut all: Vec<Range> = a;
+                    all.extend(b);
+                    all.sort_by_key(Range::cmp_key);
+                    merge_ranges(&all)
+                }
+                Op::Difference => subtract_ranges(&a, &b),
+                Op::Intersection => intersect_ranges(&a, &b),
+            })
+        }
+    }
+}
+
+/* ---------------------------------- */
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Token<'a> {
+    Op(Op),
+    Open,
+    Close,
+    Word(&'a str),
+}
+
+impl fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Op(Op::Union) => f.write_str("+"),
+            Token::Op(Op::Difference) => f.write_str("-"),
+            Token::Op(Op::Intersection) => f.write_str("&"),
+            Token::Open => f.write_str("("),
+            Token::Close => f.write_str(")"),
+            Token::Word(w) => f.write_str(w),
+        }
+    }
+}
+
+/// Split `s` into tokens with their byte spans.
+fn tokenize(s: &str) -> Vec<(Token<'_>, ops::Range<usize>)> {
+    let mut out: Vec<(Token, ops::Range<usize>)> = Vec::new();
+    let mut chars = s.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '+' => out.push((Token::Op(Op::Union), i..i + 1)),
+            '&' => out.push((Token::Op(Op::Intersection), i..i + 1)),
+            '(' => out.push((Token::Open, i..i + 1)),
+            ')' => out.push((Token::Close, i..i + 1)),
+            _ => {
+                let mut end: usize = i + c.len_utf8();
+                while let Some(&(j, d)) = chars.peek() {
+                    if d.is_whitespace() || DELIMS.contains(&d) {
+                        break;
+                    }
+                    end = j + d.len_utf8();
+                    chars.next();
+                }
+                push_word(&mut out, s, i, end);
+            }
+        }
+    }
+    out
+}
+
+/// Push a word, splitting dashes at either end off as difference operators.
+fn push_word<'a>(
+    out: &mut Vec<(Token<'a>, ops::Range<usize>)>,
+    s: &'a str,
+    beg: usize,
+    end: usize,
+) {
+    let word: &str = &s[beg..end];
+    let lead: usize = word.len() - word.trim_start_matches('-').len();
+    let core: &str = word.trim_matches('-');
+    let minus = |at: usize| (Token::Op(Op::Difference), at..at + 1);
+
+    out.extend((beg..beg + lead).map(minus));
+    if !core.is_empty() {
+        out.push((Token::Word(core), beg + lead..beg + lead + core.len()));
+        out.extend((beg + lead + core.len()..end).map(minus));
+    }
+}
+
+/// Recursive descent over `expr := term (op term)*`, `term := word | '(' expr ')'`.
+struct Parser<'a> {
+    tokens: Vec<(Token<'a>, ops::Range<usize>)>,
+    pos: usize,
+    /// length of the input, for errors at its end
+    len: usize,
+}
+
+impl Parser<'_> {
+    fn expr(&mut self) -> Result<Node, ExprError> {
+        let mut lhs: Node = self.term()?;
+        while let Some((tok, span)) = self.tokens.get(self.pos).cloned() {
+            match tok {
+                Token::Op(op) => {
+                    self.pos += 1;
+                    lhs = Node::Op(op, Box::new(lhs), Box::new(self.term()?));
+                }
+                Token::Close => break,
+                _ => {
+                    return Err(ExprError::Unexpected {
+                        span,
+                        found: tok.to_string(),
+                    })
+                }
+            }
+        }
+        Ok(lhs)
+    }
+
+    fn term(&mut self) -> Result<Node, ExprError> {
+        let Some((tok, span)) = self.tokens.get(self.pos).cloned() else {
+            return Err(ExprError::UnexpectedEnd { pos: self.len });
+        };
+        self.pos += 1;
+        match tok {
+            Token::Word(word) if is_name(word) => Ok(Node::Name(word.to_string(), span)),
+            Token::Word(word) => {
+                let range: IpRange = word.parse().map_err(|source| ExprError::InvalidLiteral {
+                    span: span.clone(),
+                    literal: word.to_string(),
+                    source,
+                })?;
+                let range: Range =
+                    iprange_to_range(range).expect("parsed ranges never mix families");
+                Ok(Node::Set(vec![range]))
+            }
+            Token::Open => {
+                let inner: Node = self.expr()?;
+                match self.tokens.get(self.pos) {
+                    Some((Token::Close, _)) => {
+                        self.pos += 1;
+                        Ok(inner)
+                    }
+                    _ => Err(ExprError::UnclosedParen { span }),
+                }
+            }
+            Token::Op(_) | Token::Close => Err(ExprError::Unexpected {
+                span,
+                found: tok.to_string(),
+            }),
+        }
+    }
+}
+
+/// Names start with a letter or `_`; they never contain the `.`/`:` of an address.
+fn is_name(word: &str) -> bool {
+    let mut chars = word.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/* -------------------------------------------------------------------------- */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RFC1918: [&str; 3] = ["10.0.0.0/8", "172.16.0.0/12", "192.168.0.0/16"];
+
+    fn names() -> BTreeMap<String, Vec<Cidr>> {
+        let rfc1918: Vec<Cidr> = RFC1918.iter().map(|s| s.parse().unwrap()).collect();
+        BTreeMap::from([("rfc1918".to_string(), rfc1918)])
+    }
+
+    fn eval(expr: &str) -> Result<Vec<String>, ExprError> {
+        Ok(eval_expr(expr, &names())?
+            .iter()
+            .map(|c| c.to_string())
+            .collect())
+    }
+
+    #[test]
+    fn test_eval() {
+        assert_eq!(
+            eval("10.0.0.0/8 - 10.1.0.0/16 + 192.168.1.5-20 & rfc1918").unwrap(),
+            eval("((10.0.0.0/8 - 10.1.0.0/16) + 192.168.1.5-20) & rfc1918").unwrap(),
+        );
+        assert_eq!(
+            eval("10.0.0.0/30 + 10.0.0.4/30 - 10.0.0.7").unwrap(),
+            ["10.0.0.0/30", "10.0.0.4/31", "10.0.0.6/32"]
+        );
+        // precedence is left to right, parentheses override it
+        assert_eq!(
+            eval("8.8.8.8 + 10.0.0.1 & rfc1918").unwrap(),
+            ["10.0.0.1/32"]
+        );
+        assert_eq!(
+            eval("8.8.8.8 + (10.0.0.1 & rfc1918)").unwrap(),
+            ["8.8.8.8/32", "10.0.0.1/32"]
+        );
+        // dashes glued to a literal are operators only at its ends
+        assert_eq!(
+            eval("(10.0.0.0-10.0.0.3)-10.0.0.3").unwrap(),
+            ["10.0.0.0/31", "10.0.0.2/32"]
+        );
+        assert_eq!(
+            eval("fe80::/64%eth0 & fe80::1%eth0 + 2001:db8::/32").unwrap(),
+            ["2001:db8::/32", "fe80::1%eth0/128"]
+        );
+
+        let expr: Expr = "(a + b) - rfc1918 & a".parse().unwrap();
+        assert_eq!(expr.names(), ["a", "b", "rfc1918"]);
+    }
+
+    #[test]
+    fn test_errors() {
+        #[rustfmt::skip]
+        let cases: [(&str, ops::Range<usize>); 7] = [
+            ("",                        0..0),
+            ("10.0.0.0/8 +",            12..12),
+            ("10.0.0.0/8 10.0.0.1",     11..19),
+            ("(10.0.0.0/8 - lan",       0..1),
+            ("10.0.0.0/8)",             10..11),
+            ("10.0.0.0/33 + rfc1918",   0..11),
+            ("rfc1918 - lan",           10..13),
+        ];
+        for (expr, span) in cases {
+            assert_eq!(eval(expr).unwrap_err().span(), span, "{expr}");
+        }
+        let err: ExprError = eval("10.0.0.0/33 + rfc1918").unwrap_err();
+        assert_eq!(err.to_string(), format!("{ERR_EXPR_LITERAL} '10.0.0.0/33'"));
+        assert!(error::Error::source(&err).unwrap().is::<AddressError>());
+        assert!(matches!(
+            eval("10.0.0.0/8 & nope"),
+            Err(ExprError::UnknownName { name, .. }) if name == "nope"
+        ));
+        assert_eq!(
+            eval("rfc1918 - lan").unwrap_err().render("rfc1918 - lan"),
+            "rfc1918 - lan\n          ^^^ unknown name 'lan'"
+        );
+    }
+}
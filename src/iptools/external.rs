@@ -0,0 +1,657 @@
+// Copyright (c) 2026 Mikko Tanner. All rights reserved.
+// Licensed under the MIT License or the Apache License, Version 2.0.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! External-memory collapsing for address lists which do not fit in RAM.
+
+use super::{
+    collapsing::{iprange_to_range, range_to_cidrs},
+    strings::*,
+    structs::{Cidr, IpFam, IpRange, Range},
+    zone::{ZoneId, ZONE_MAX_LEN},
+    AddressError,
+};
+use crate::str_to_bytes;
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, VecDeque},
+    env, error, fmt,
+    fs::{self, File, OpenOptions},
+    io::{self, BufReader, BufWriter, Read, Write},
+    mem::size_of,
+    path::PathBuf,
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+    vec,
+};
+
+const DEFAULT_MEM_LIMIT: usize = 256 * 1024 * 1024;
+const MIN_RUN_LEN: usize = 16; // never spill runs shorter than this (in ranges)
+const RECORD_LEN: usize = 2 + ZONE_MAX_LEN + 32; // family (1) + zone len (1) + zone + beg (16) + end (16)
+const ZONE_AT: usize = 2;
+const BEG_AT: usize = ZONE_AT + ZONE_MAX_LEN;
+const END_AT: usize = BEG_AT + 16;
+const MIN_READ_BUF: usize = 4096;
+const MAX_READ_BUF: usize = 1024 * 1024;
+const MAX_MERGE_RUNS: usize = 128; // open spill files per merge, well below common fd limits
+
+/// Sequence number for spill file names, unique within the process.
+static RUN_SEQ: AtomicUsize = AtomicUsize::new(0);
+
+type HeapKey = Reverse<((u8, Option<ZoneId>, u128, u128), usize)>;
+
+#[derive(Debug)]
+pub enum ExternalError {
+    /// reading or writing a spill file failed
+    Io(io::Error),
+    /// an input item was not a valid address/range
+    Address(AddressError),
+    /// memory limit string could not be parsed
+    MemLimit {
+        input: String,
+        source: Box<crate::Error>,
+    },
+    /// memory limit (in bytes) does not fit in a `usize`
+    MemLimitTooLarge(u128),
+    /// spill file ended mid-record or contained garbage
+    Corrupt(PathBuf),
+}
+
+impl fmt::Display for ExternalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExternalError::Io(_) => f.write_str(ERR_EXT_IO),
+            ExternalError::Address(e) => write!(f, "{e}"),
+            ExternalError::MemLimit { input, .. } => write!(f, "{ERR_EXT_MEMLIMIT}: '{input}'"),
+            ExternalError::MemLimitTooLarge(n) => write!(f, "{ERR_EXT_MEMLIMIT_SIZE}: {n}"),
+            ExternalError::Corrupt(p) => write!(f, "{ERR_EXT_CORRUPT}: '{}'", p.display()),
+        }
+    }
+}
+
+impl error::Error for ExternalError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ExternalError::Io(e) => Some(e),
+            ExternalError::MemLimit { source, .. } => Some(source.as_ref()),
+            // transparent: displayed as the address error itself
+            ExternalError::Address(e) => e.source(),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ExternalError {
+    fn from(e: io::Error) -> Self {
+        ExternalError::Io(e)
+    }
+}
+
+impl From<AddressError> for ExternalError {
+    fn from(e: AddressError) -> Self {
+        ExternalError::Address(e)
+    }
+}
+
+/* ---------------------------------- */
+
+/**
+Collapser for inputs larger than the memory budget of the host.
+
+Input is buffered up to the memory limit, after which the buffer is sorted,
+merged and spilled to a temporary file ("run"). Once the input is exhausted
+the runs are k-way merged and the minimal CIDRs are emitted as a stream, so
+neither the input nor the output need to fit in memory at once. If the whole
+input fits within the limit, nothing is written to disk.
+
+At most 128 runs are read at once. With more runs than that (a small
+memory limit and a large input), groups of runs are first merged into
+longer runs on disk, which costs one extra read and write of the data per
+pass.
+
+```ignore
+let stream = ExternalCollapser::new()
+    .with_mem_limit_str("512M")?
+    .with_tmp_dir("/var/tmp")
+    .collapse(feed)?;
+for cidr in stream {
+    println!("{}", cidr?);
+}
+```
+*/
+#[derive(Clone, Debug)]
+pub struct ExternalCollapser {
+    mem_limit: usize,
+    tmp_dir: PathBuf,
+    max_gap: u128,
+}
+
+impl Default for ExternalCollapser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ExternalCollapser {
+    /// New collapser with a 256 MiB memory limit, spilling to [env::temp_dir].
+    pub fn new() -> Self {
+        Self {
+            mem_limit: DEFAULT_MEM_LIMIT,
+            tmp_dir: env::temp_dir(),
+            max_gap: 0,
+        }
+    }
+
+    /// Build with a memory limit (in bytes) for buffered input.
+    pub fn with_mem_limit(mut self, bytes: usize) -> Self {
+        self.mem_limit = bytes;
+        self
+    }
+
+    /// Build with a memory limit given as a size string, f.ex. `"512M"`.
+    /// See [str_to_bytes] for the accepted notations.
+    pub fn with_mem_limit_str(self, limit: &str) -> Result<Self, ExternalError> {
+        let bytes: u128 = str_to_bytes(limit).map_err(|e| ExternalError::MemLimit {
+            input: limit.to_string(),
+            source: Box::new(e),
+        })?;
+        let bytes: usize =
+            usize::try_from(bytes).map_err(|_| ExternalError::MemLimitTooLarge(bytes))?;
+        Ok(self.with_mem_limit(bytes))
+    }
+
+    /// Build with a directory for the temporary spill files.
+    pub fn with_tmp_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.tmp_dir = dir.into();
+        self
+    }
+
+    /// Build with fuzzy merging of ranges separated by <= `max_gap` IPs.
+    pub fn with_max_gap(mut self, max_gap: u128) -> Self {
+        self.max_gap = max_gap;
+        self
+    }
+
+    /**
+    Consume `input` (IPs, CIDRs or ranges) and return a stream of minimal CIDRs.
+
+    The input is fully consumed (and spilled as needed) before this returns;
+    the final merge happens lazily while iterating the returned stream.
+    Spill files are removed when the stream is dropped.
+    */
+    pub fn collapse<I, T>(&self, input: I) -> Result<CollapseStream, ExternalError>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<IpRange>,
+    {
+        let cap: usize = self.run_capacity();
+        let mut buf: Vec<Range> = Vec::new();
+        let mut runs: Vec<SpillRun> = Vec::new();
+
+        for item in input {
+            if buf.len() == cap {
+                runs.push(self.spill(&mut buf)?);
+            }
+            if buf.len() == buf.capacity() {
+                // grow geometrically, but never past the memory limit
+                let extra: usize = buf.capacity().max(MIN_RUN_LEN).min(cap - buf.len());
+                buf.reserve_exact(extra);
+            }
+            buf.push(iprange_to_range(item.into())?);
+        }
+
+        // Everything fit in memory: no need to touch the disk at all
+        if runs.is_empty() {
+            sort_and_merge(&mut buf);
+            return Ok(CollapseStream::new(
+                Source::Memory(buf.into_iter()),
+                self.max_gap,
+            ));
+        }
+
+        if !buf.is_empty() {
+            runs.push(self.spill(&mut buf)?);
+        }
+        drop(buf);
+
+        // Keep the number of simultaneously open files bounded
+        while runs.len() > MAX_MERGE_RUNS {
+            runs = self.merge_pass(runs)?;
+        }
+
+        Ok(CollapseStream::new(self.merge_source(runs)?, self.max_gap))
+    }
+
+    /// How many ranges fit in the buffer under the memory limit.
+    fn run_capacity(&self) -> usize {
+        (self.mem_limit / size_of::<Range>()).max(MIN_RUN_LEN)
+    }
+
+    /// Sort, merge and write the buffer out as a new run. Clears the buffer.
+    fn spill(&self, buf: &mut Vec<Range>) -> Result<SpillRun, ExternalError> {
+        sort_and_merge(buf);
+
+        let (run, mut w): (SpillRun, BufWriter<File>) = self.new_run()?;
+        for r in buf.drain(..) {
+            w.write_all(&encode_record(r))?;
+        }
+        w.flush()?;
+        Ok(run)
+    }
+
+    /// Create a new, empty spill file for writing.
+    fn new_run(&self) -> Result<(SpillRun, BufWriter<File>), ExternalError> {
+        let seq: usize = RUN_SEQ.fetch_add(1, Ordering::Relaxed);
+        let path: PathBuf = self
+            .tmp_dir
+            .join(format!("miniutils-collapse-{}-{seq}.run", process::id()));
+        let file: File = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+
+        // From here on the run owns the file and removes it on drop
+        Ok((SpillRun { path, reader: None }, BufWriter::new(file)))
+    }
+
+    /// Open `runs` for a k-way merge, sharing the memory limit between them.
+    fn merge_source(&self, mut runs: Vec<SpillRun>) -> Result<Source, ExternalError> {
+        let buf_size: usize = (self.mem_limit / runs.len()).clamp(MIN_READ_BUF, MAX_READ_BUF);
+        let mut heap: BinaryHeap<HeapKey> = BinaryHeap::with_capacity(runs.len());
+        for (idx, run) in runs.iter_mut().enumerate() {
+            run.open(buf_size)?;
+            if let Some(r) = run.next_range()? {
+                heap.push(Reverse((r.cmp_key(), idx)));
+            }
+        }
+        Ok(Source::Runs { runs, heap })
+    }
+
+    /// Merge each group of up to [MAX_MERGE_RUNS] runs into a single run.
+    /// The merged runs are removed as soon as their group is written out.
+    fn merge_pass(&self, runs: Vec<SpillRun>) -> Result<Vec<SpillRun>, ExternalError> {
+        let mut merged: Vec<SpillRun> = Vec::with_capacity(runs.len().div_ceil(MAX_MERGE_RUNS));
+        let mut runs: vec::IntoIter<SpillRun> = runs.into_iter();
+        loop {
+            let group: Vec<SpillRun> = runs.by_ref().take(MAX_MERGE_RUNS).collect();
+            if group.is_empty() {
+                return Ok(merged);
+            }
+
+            let mut source: Source = self.merge_source(group)?;
+            let (run, mut w): (SpillRun, BufWriter<File>) = self.new_run()?;
+            let mut current: Option<Range> = None;
+            while let Some(r) = source.next_range()? {
+                if let Some(cur) = current.as_mut() {
+                    if cur.space() == r.space() && r.beg <= cur.end.saturating_add(1) {
+                        cur.end = cur.end.max(r.end);
+                        continue;
+                    }
+                }
+                if let Some(prev) = current.replace(r) {
+                    w.write_all(&encode_record(prev))?;
+                }
+            }
+            if let Some(prev) = current {
+                w.write_all(&encode_record(prev))?;
+            }
+            w.flush()?;
+            merged.push(run);
+        }
+    }
+}
+
+/* ---------------------------------- */
+
+/// A sorted run of ranges spilled to disk. The file is removed on drop.
+struct SpillRun {
+    path: PathBuf,
+    reader: Option<BufReader<File>>,
+}
+
+impl SpillRun {
+    fn open(&mut self, buf_size: usize) -> Result<(), ExternalError> {
+        let file: File = File::open(&self.path)?;
+        self.reader = Some(BufReader::with_capacity(buf_size, file));
+        Ok(())
+    }
+
+    /// Read the next range from the run, or None at a clean end of file.
+    fn next_range(&mut self) -> Result<Option<Range>, ExternalError> {
+        let reader: &mut BufReader<File> = match self.reader.as_mut() {
+            Some(r) => r,
+            None => return Ok(None),
+        };
+
+        let mut rec: [u8; RECORD_LEN] = [0; RECORD_LEN];
+        let mut filled: usize = 0;
+        while filled < RECORD_LEN {
+            match reader.read(&mut rec[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        match filled {
+            0 => {
+                self.reader = None;
+                Ok(None)
+            }
+            RECORD_LEN => match decode_record(&rec) {
+                Some(r) => Ok(Some(r)),
+                None => Err(ExternalError::Corrupt(self.path.clone())),
+            },
+            _ => Err(ExternalError::Corrupt(self.path.clone())),
+        }
+    }
+}
+
+impl Drop for SpillRun {
+    fn drop(&mut self) {
+        self.reader = None;
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Like [merge_ranges](super::collapsing::merge_ranges), but in place so that
+/// the buffer is never duplicated in memory.
+fn sort_and_merge(buf: &mut Vec<Range>) {
+    buf.sort_by_key(Range::cmp_key);
+    buf.dedup_by(|r, last| {
+        let merge: bool = last.space() == r.space() && r.beg <= last.end.saturating_add(1);
+        if merge {
+            last.end = last.end.max(r.end);
+        }
+        merge
+    });
+}
+
+fn encode_record(r: Range) -> [u8; RECORD_LEN] {
+    let mut rec: [u8; RECORD_LEN] = [0; RECORD_LEN];
+    rec[0] = r.cmp_key().0;
+    if let Some(zone) = r.zone {
+        let z: &[u8] = zone.as_str().as_bytes();
+        rec[1] = z.len() as u8;
+        rec[ZONE_AT..ZONE_AT + z.len()].copy_from_slice(z);
+    }
+    rec[BEG_AT..END_AT].copy_from_slice(&r.beg.to_le_bytes());
+    rec[END_AT..].copy_from_slice(&r.end.to_le_bytes());
+    rec
+}
+
+fn decode_record(rec: &[u8; RECORD_LEN]) -> Option<Range> {
+    let fam: IpFam = fam_from_key(rec[0])?;
+    let zone: Option<ZoneId> = match rec[1] as usize {
+        0 => None,
+        n if n <= ZONE_MAX_LEN => Some(
+            std::str::from_utf8(&rec[ZONE_AT..ZONE_AT + n])
+                .ok()?
+                .parse()
+                .ok()?,
+        ),
+        _ => return None,
+    };
+    let beg: u128 = u128::from_le_bytes(rec[BEG_AT..END_AT].try_into().ok()?);
+    let end: u128 = u128::from_le_bytes(rec[END_AT..].try_into().ok()?);
+    if beg > end || (fam == IpFam::V4 && (end > u32::MAX as u128 || zone.is_some())) {
+        return None;
+    }
+    Some(Range {
+        fam,
+        zone,
+        beg,
+        end,
+    })
+}
+
+/// Inverse of the family component of [Range::cmp_key].
+#[inline]
+fn fam_from_key(key: u8) -> Option<IpFam> {
+    match key {
+        0 => Some(IpFam::V4),
+        1 => Some(IpFam::V6),
+        _ => None,
+    }
+}
+
+/* ---------------------------------- */
+
+enum Source {
+    Memory(vec::IntoIter<Range>),
+    Runs {
+        runs: Vec<SpillRun>,
+        heap: BinaryHeap<HeapKey>,
+    },
+}
+
+impl Source {
+    /// Next range in global sort order.
+    fn next_range(&mut self) -> Result<Option<Range>, ExternalError> {
+        match self {
+            Source::Memory(it) => Ok(it.next()),
+            Source::Runs { runs, heap } => {
+                let Some(Reverse(((fam, zone, beg, end), idx))) = heap.pop() else {
+                    return Ok(None);
+                };
+                if let Some(r) = runs[idx].next_range()? {
+                    heap.push(Reverse((r.cmp_key(), idx)));
+                }
+                let fam: IpFam = fam_from_key(fam).expect("family key comes from a Range");
+                Ok(Some(Range {
+                    fam,
+                    zone,
+                    beg,
+                    end,
+                }))
+            }
+        }
+    }
+}
+
+/**
+Stream of minimal CIDRs produced by [ExternalCollapser::collapse].
+
+Yields CIDRs in ascending order (IPv4 first). An I/O error while reading
+the spill files is yielded once, after which the stream ends.
+*/
+pub struct CollapseStream {
+    source: Source,
+    max_gap: u128,
+    current: Option<Range>,
+    pending: VecDeque<Cidr>,
+    done: bool,
+}
+
+impl CollapseStream {
+    fn new(source: Source, max_gap: u128) -> Self {
+        Self {
+            source,
+            max_gap,
+            current: None,
+            pending: VecDeque::new(),
+            done: false,
+        }
+    }
+}
+
+impl Iterator for CollapseStream {
+    type Item = Result<Cidr, ExternalError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(cidr) = self.pending.pop_front() {
+                return Some(Ok(cidr));
+            }
+            if self.done {
+                return None;
+            }
+
+            match self.source.next_range() {
+                Ok(Some(r)) => {
+                    if let Some(cur) = self.current.as_mut() {
+                        // overlapping, adjacent or within the fuzzy gap?
+                        let gap: u128 = r.beg.saturating_sub(cur.end.saturating_add(1));
+                        if cur.space() == r.space() && gap <= self.max_gap {
+                            cur.end = cur.end.max(r.end);
+                            continue;
+                        }
+                    }
+                    if let Some(prev) = self.current.replace(r) {
+                        self.pending.extend(range_to_cidrs(prev));
+                    }
+                }
+                Ok(None) => {
+                    self.done = true;
+                    if let Some(prev) = self.current.take() {
+                        self.pending.extend(range_to_cidrs(prev));
+                    }
+                }
+                Err(e) => {
+                    self.done = true;
+                    self.pending.clear();
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+/* -------------------------------------------------------------------------- */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::iptools::{collapse_cidrs, collapse_ranges};
+    use std::net::IpAddr;
+
+    const RANGES: [(&str, &str); 4] = [
+        ("10.0.0.0", "10.0.0.255"),
+        ("10.0.1.0", "10.0.1.127"),
+        ("2001:db8::", "2001:db8::ff"),
+        ("10.0.0.128", "10.0.2.0"),
+    ];
+
+    /// Deterministic pseudo-random CIDRs, mixed families and zones.
+    fn generate(n: usize) -> Vec<Cidr> {
+        let mut x: u64 = 0x9e37_79b9_7f4a_7c15;
+        (0..n)
+            .map(|i| {
+                x ^= x << 13;
+                x ^= x >> 7;
+                x ^= x << 17;
+                if i % 3 == 0 {
+                    let addr: IpAddr =
+                        IpAddr::V6(((0x2001_0db8u128 << 96) | (x as u128 & 0xffff)).into());
+                    // every other IPv6 block is link-local style, in a zone
+                    Cidr {
+                        addr,
+                        prefix: 120 + (x % 9) as u8,
+                        zone: None,
+                    }
+                    .with_zone((i % 6 == 0).then(|| "eth0".parse().unwrap()))
+                } else {
+                    let addr: IpAddr =
+                        IpAddr::V4((0x0a00_0000u32 | (x as u32 & 0x000f_ffff)).into());
+                    Cidr {
+                        addr,
+                        prefix: 24 + (x % 9) as u8,
+                        zone: None,
+                    }
+                }
+            })
+            .collect()
+    }
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir: PathBuf = env::temp_dir().join(format!("miniutils-{name}-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_in_memory() {
+        let input: Vec<IpRange> = RANGES
+            .iter()
+            .map(|(b, e)| IpRange::new(b.parse().unwrap(), e.parse().unwrap()).unwrap())
+            .collect();
+        let out: Vec<Cidr> = ExternalCollapser::new()
+            .collapse(input.clone())
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(out, collapse_ranges(&input).unwrap());
+    }
+
+    #[test]
+    fn test_spilled_matches_in_memory() {
+        let dir: PathBuf = test_dir("spill");
+        let input: Vec<Cidr> = generate(2000);
+        for gap in [0u128, 3, 300] {
+            let stream: CollapseStream = ExternalCollapser::new()
+                .with_mem_limit(0)
+                .with_tmp_dir(&dir)
+                .with_max_gap(gap)
+                .collapse(input.iter().copied())
+                .unwrap();
+            assert!(
+                fs::read_dir(&dir).unwrap().count() > 1,
+                "nothing was spilled"
+            );
+            let out: Vec<Cidr> = stream.collect::<Result<_, _>>().unwrap();
+            assert_eq!(out, collapse_cidrs(&input, gap), "max_gap {gap}");
+        }
+        // spill files are gone once the streams are dropped
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+        fs::remove_dir(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_multi_pass_merge() {
+        let dir: PathBuf = test_dir("multipass");
+        // enough input for well over MAX_MERGE_RUNS runs at the minimum run length
+        let input: Vec<Cidr> = generate(MAX_MERGE_RUNS * MIN_RUN_LEN * 3);
+        let stream: CollapseStream = ExternalCollapser::new()
+            .with_mem_limit(0)
+            .with_tmp_dir(&dir)
+            .collapse(input.iter().copied())
+            .unwrap();
+        let spilled: usize = fs::read_dir(&dir).unwrap().count();
+        assert!(
+            (2..=MAX_MERGE_RUNS).contains(&spilled),
+            "{spilled} runs left"
+        );
+        let out: Vec<Cidr> = stream.collect::<Result<_, _>>().unwrap();
+        assert_eq!(out, collapse_cidrs(&input, 0));
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+        fs::remove_dir(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_mixed_input_kinds() {
+        let ip: IpAddr = "192.168.0.1".parse().unwrap();
+        let cidr: Cidr = "192.168.0.0/31".parse().unwrap();
+        let mut stream: CollapseStream = ExternalCollapser::new()
+            .collapse([IpRange::from(ip), IpRange::from(cidr)])
+            .unwrap();
+        assert_eq!(stream.next().unwrap().unwrap(), cidr);
+        assert!(stream.next().is_none());
+    }
+
+    #[test]
+    fn test_mem_limit_str() {
+        let c: ExternalCollapser = ExternalCollapser::new().with_mem_limit_str("512M").unwrap();
+        assert_eq!(c.mem_limit, 512 * 1024 * 1024);
+        let err: ExternalError = ExternalCollapser::new()
+            .with_mem_limit_str("lots")
+            .unwrap_err();
+        assert_eq!(err.to_string(), format!("{ERR_EXT_MEMLIMIT}: 'lots'"));
+        // the cause is kept, typed
+        let cause = error::Error::source(&err).unwrap();
+        assert!(matches!(
+            cause.downcast_ref::<crate::Error>(),
+            Some(crate::Error::ByteSize { .. })
+        ));
+    }
+}
@@ -0,0 +1,207 @@
+// Copyright (c) 2026 Mikko Tanner. All rights reserved.
+// Licensed under the MIT License or the Apache License, Version 2.0.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+/*!
+Extraction of addresses, CIDRs and ranges from free text, such as tickets,
+emails and log lines.
+
+Candidates are found with a loose pattern, then validated by parsing them
+as an [AddressSpec] and checking their surroundings: an address must not be
+glued to a word (`v1.2.3.4`, `std::vec`) or be part of a longer dotted or
+colon-separated token (`1.2.3.4.5`, MAC addresses, timestamps). Defanged
+forms are accepted: `10[.]0[.]0[.]1`, `10(dot)0(dot)0(dot)1`, `2001[:]db8::1`.
+*/
+
+use super::spec::AddressSpec;
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use std::ops::Range;
+
+lazy_static! {
+    static ref CANDIDATE_RX: Regex = {
+        let dot: &str = r"(?:\.|\[\.\]|\(\.\)|\{\.\}|\[dot\]|\(dot\))";
+        let colon: &str = r"(?::|\[:\])";
+        let v4: String = format!(r"\d{{1,3}}(?:{dot}\d{{1,3}}){{3}}");
+        let v6: String = format!(
+            r"[0-9A-Fa-f]{{0,4}}(?:{colon}[0-9A-Fa-f]{{0,4}}){{2,8}}(?:%[0-9A-Za-z_]+(?:[.\-][0-9A-Za-z_]+)*)?"
+        );
+        let addr: String = format!("(?:{v4}|{v6})");
+        Regex::new(&format!(
+            r"(?P<spec>{addr}(?:/\d{{1,3}})?)(?:\s?-\s?{addr}|-\d{{1,5}})?"
+        ))
+        .unwrap()
+    };
+}
+
+/// Defanged separators and their plain forms.
+#[rustfmt::skip]
+static DEFANGED: &[(&str, &str)] = &[
+    ("[.]", "."), ("(.)", "."), ("{.}", "."), ("[dot]", "."), ("(dot)", "."), ("[:]", ":"),
+];
+
+/// An address, CIDR or range found by [extract_addresses].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Extracted {
+    /// byte offsets in the text
+    pub span: Range<usize>,
+    pub spec: AddressSpec,
+    /// whether the text used a defanged form
+    pub defanged: bool,
+}
+
+impl Extracted {
+    /// The matched part of `text`, which must be the text this was extracted from.
+    pub fn as_str<'a>(&self, text: &'a str) -> &'a str {
+        &text[self.span.clone()]
+    }
+}
+
+/// Find every valid address, CIDR and range in `text`, in order of appearance.
+pub fn extract_addresses(text: &str) -> Vec<Extracted> {
+    CANDIDATE_RX
+        .captures_iter(text)
+        .filter_map(|caps| validate(text, &caps))
+        .collect()
+}
+
+/**
+Rewrite each hit of [extract_addresses] in `text` with the output of `f`,
+f.ex. to redact addresses or [anonymize](super::PrefixAnonymizer) them.
+*/
+pub fn replace_ips<F: FnMut(&Extracted) -> String>(text: &str, mut f: F) -> String {
+    let mut out: String = String::with_capacity(text.len());
+    let mut pos: usize = 0;
+    for hit in extract_addresses(text) {
+        out.push_str(&text[pos..hit.span.start]);
+        out.push_str(&f(&hit));
+        pos = hit.span.end;
+    }
+    out.push_str(&text[pos..]);
+    out
+}
+
+/* ---------------------------------- */
+
+/// Accept a candidate as a whole, or without its range end.
+fn validate(text: &str, caps: &Captures) -> Option<Extracted> {
+    let (whole, spec) = (caps.get(0)?, caps.name("spec")?);
+    [whole.end(), spec.end()]
+        .into_iter()
+        .find_map(|end| accept(text, trim(text, whole.start()..end)))
+}
+
+/// Drop a leading single `:` and trailing sentence punctuation, but keep `::`.
+fn trim(text: &str, mut span: Range<usize>) -> Range<usize> {
+    let s: &str = &text[span.clone()];
+    if s.starts_with(':') && !s.starts_with("::") {
+        span.start += 1;
+    }
+    let s: &str = &text[span.clone()];
+    if s.ends_with('.') || (s.ends_with(':') && !s.ends_with("::")) {
+        span.end -= 1;
+    }
+    span
+}
+
+fn accept(text: &str, span: Range<usize>) -> Option<Extracted> {
+    let raw: &str = &text[span.clone()];
+    let plain: String = DEFANGED
+        .iter()
+        .fold(raw.to_string(), |s, (from, to)| s.replace(from, to));
+    let spec: AddressSpec = plain.parse().ok()?;
+    let v4: bool = spec.to_range().beg.is_ipv4();
+    // a bare `::` is more likely punctuation than the unspecified address
+    let cidr: bool = matches!(spec, AddressSpec::Cidr(_));
+    if !v4 && !cidr && !plain.chars().any(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    bounded(text, &span, v4).then_some(Extracted {
+        span,
+        spec,
+        defanged: plain != raw,
+    })
+}
+
+/**
+Whether the span stands on its own: no word character or `.` before it, and
+after it either nothing, a non-word character, or `.`/`:` which does not
+continue the token (an IPv4 `:port` is fine).
+*/
+fn bounded(text: &str, span: &Range<usize>, v4: bool) -> bool {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    if text[..span.start]
+        .chars()
+        .next_back()
+        .is_some_and(|c| is_word(c) || c == '.')
+    {
+        return false;
+    }
+    let mut after = text[span.end..].chars();
+    match (after.next(), after.next()) {
+        (Some(c), _) if is_word(c) => false,
+        (Some(':'), Some(d)) if v4 && d.is_ascii_digit() => true,
+        (Some('.' | ':'), Some(d)) => !is_word(d),
+        _ => true,
+    }
+}
+
+/* -------------------------------------------------------------------------- */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn found(text: &str) -> Vec<&str> {
+        extract_addresses(text)
+            .iter()
+            .map(|e| e.as_str(text))
+            .collect()
+    }
+
+    #[test]
+    fn test_extract() {
+        #[rustfmt::skip]
+        let cases: [(&str, &[&str]); 14] = [
+            ("blocked 10.0.0.1 and 192.168.0.0/16.",        &["10.0.0.1", "192.168.0.0/16"]),
+            ("range 10.0.0.1-10.0.0.9, short 10.1.1.1-20",  &["10.0.0.1-10.0.0.9", "10.1.1.1-20"]),
+            ("from 10.0.0.1 - 10.0.0.9 only",               &["10.0.0.1 - 10.0.0.9"]),
+            ("conn [2001:db8::1]:443 src:10.0.0.5:5060",    &["2001:db8::1", "10.0.0.5"]),
+            ("link fe80::1%eth0, route ::/0",               &["fe80::1%eth0", "::/0"]),
+            ("addr 2001:db8::5: refused",                   &["2001:db8::5"]),
+            ("ioc 10[.]0[.]0[.]1 and 8(dot)8(dot)8(dot)8",  &["10[.]0[.]0[.]1", "8(dot)8(dot)8(dot)8"]),
+            // false positives
+            ("version v1.2.3.4 or 1.2.3 or 1.2.3.4.5",      &[]),
+            ("at 2026-01-02T10:20:30Z, 12:30:45 elapsed",   &[]),
+            ("mac 00:1a:2b:3c:4d:5e, std::vec, a :: b",     &[]),
+            ("bad 256.1.1.1 and 01.2.3.4 or 10.0.0.0/33",   &[]),
+            ("oid 1.3.6.1.4.1.9 and 10.0.0.1.",             &["10.0.0.1"]),
+            ("10.0.0.9-1 is not a range",                   &["10.0.0.9"]),
+            ("",                                            &[]),
+        ];
+        for (text, want) in cases {
+            assert_eq!(found(text), want, "{text}");
+        }
+
+        let hits: Vec<Extracted> = extract_addresses("x 10[.]0[.]0[.]0/8 é 10.0.0.1");
+        assert!(hits[0].defanged && !hits[1].defanged);
+        assert_eq!(hits[0].spec.to_string(), "10.0.0.0/8");
+        assert_eq!(hits[1].span, 22..30);
+    }
+
+    #[test]
+    fn test_replace_ips() {
+        let text: &str = "Failed login from 203.0.113.7 (2001:db8::7), see 203[.]0[.]113[.]0/24";
+        let redacted: String = replace_ips(text, |_| "[REDACTED]".into());
+        assert_eq!(
+            redacted,
+            "Failed login from [REDACTED] ([REDACTED]), see [REDACTED]"
+        );
+        let normalized: String = replace_ips(text, |e| e.spec.to_string());
+        assert!(normalized.ends_with("see 203.0.113.0/24"));
+        assert_eq!(
+            replace_ips("no addresses", |_| unreachable!()),
+            "no addresses"
+        );
+    }
+}
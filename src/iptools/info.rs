@@ -0,0 +1,289 @@
+// Copyright (c) 2026 Mikko Tanner. All rights reserved.
+// Licensed under the MIT License or the Apache License, Version 2.0.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! ipcalc-style subnet information.
+
+use super::{
+    collapsing::{cidr_to_range, int_to_ip},
+    prelude::*,
+    structs::{Cidr, IpFam, IpRange, IpRangeIterator, Range},
+    IPV4_BITS, IPV6_BITS,
+};
+#[cfg(feature = "tabulate")]
+use crate::simple_tabulate;
+use core::{fmt, net::IpAddr};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+static V4_REVERSE: &str = "in-addr.arpa";
+static V6_REVERSE: &str = "ip6.arpa";
+
+/// Classful network class of an IPv4 address (historical, but still asked about).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Ipv4Class {
+    A,
+    B,
+    C,
+    /// multicast
+    D,
+    /// reserved
+    E,
+}
+
+impl fmt::Display for Ipv4Class {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s: &str = match self {
+            Ipv4Class::A => "A",
+            Ipv4Class::B => "B",
+            Ipv4Class::C => "C",
+            Ipv4Class::D => "D",
+            Ipv4Class::E => "E",
+        };
+        f.write_str(s)
+    }
+}
+
+/// Subnet information for a [Cidr], see [Cidr::info].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CidrInfo {
+    /// the normalized network, f.ex. `10.0.0.0/24` for `10.0.0.5/24`
+    pub cidr: Cidr,
+    pub network: IpAddr,
+    /// IPv4 only, and not for /31 or /32
+    pub broadcast: Option<IpAddr>,
+    pub netmask: IpAddr,
+    /// inverse of the netmask (host mask)
+    pub wildcard: IpAddr,
+    pub first_host: IpAddr,
+    pub last_host: IpAddr,
+    /// number of usable host addresses (`first_host..=last_host`)
+    pub hosts: u128,
+    /// total number of addresses in the network. Saturating.
+    pub addresses: u128,
+    /// IPv4 only
+    pub class: Option<Ipv4Class>,
+    /// the enclosing reverse DNS zone (octet/nibble aligned)
+    pub reverse_zone: String,
+}
+
+impl Cidr {
+    /**
+    ipcalc-style information about this network.
+
+    Usable hosts follow the same rules as [Cidr::hosts].
+    */
+    pub fn info(&self) -> CidrInfo {
+        let range: Range = cidr_to_range(*self);
+        let (first, last) = host_bounds(range, self.prefix);
+        let bits: u8 = fam_bits(range.fam);
+        let all_ones: u128 = match range.fam {
+            IpFam::V4 => u32::MAX as u128,
+            IpFam::V6 => u128::MAX,
+        };
+        let wildcard: u128 = range.end ^ range.beg;
+
+        let broadcast: Option<IpAddr> = match range.fam {
+            IpFam::V4 if self.prefix < IPV4_BITS - 1 => Some(int_to_ip(range.fam, range.end)),
+            _ => None,
+        };
+
+        CidrInfo {
+            cidr: self.network(),
+            network: int_to_ip(range.fam, range.beg),
+            broadcast,
+            netmask: int_to_ip(range.fam, all_ones & !wildcard),
+            wildcard: int_to_ip(range.fam, wildcard),
+            first_host: int_to_ip(range.fam, first),
+            last_host: int_to_ip(range.fam, last),
+            hosts: last - first + 1,
+            addresses: range.len(),
+            class: match self.addr {
+                IpAddr::V4(a) => Some(ipv4_class(a.octets()[0])),
+                IpAddr::V6(_) => None,
+            },
+            reverse_zone: reverse_zone(range, self.prefix.min(bits)),
+        }
+    }
+
+    /**
+    Returns an iterator over the usable host addresses of the network.
+
+    - **IPv4**: network and broadcast addresses are excluded, except for
+      /31 (point-to-point, RFC 3021) and /32 where every address is a host.
+    - **IPv6**: there is no broadcast, but the Subnet-Router anycast address
+      (the network address, RFC 4291) is excluded, except for /127
+      (point-to-point, RFC 6164) and /128.
+
+    Use [Cidr::iter] to iterate over *all* addresses instead.
+    */
+    pub fn hosts(&self) -> IpRangeIterator {
+        let range: Range = cidr_to_range(*self);
+        let (first, last) = host_bounds(range, self.prefix);
+        IpRange {
+            beg: int_to_ip(range.fam, first),
+            end: int_to_ip(range.fam, last),
+            zone: self.zone,
+        }
+        .iter()
+    }
+}
+
+impl CidrInfo {
+    /// Render the information as a two-column table for printing.
+    #[cfg(feature = "tabulate")]
+    pub fn to_table(&self) -> Vec<String> {
+        let opt = |v: Option<String>| v.unwrap_or_else(|| "-".to_string());
+        let rows: Vec<[String; 2]> = vec![
+            ["Network".into(), self.cidr.to_string()],
+            ["Netmask".into(), self.netmask.to_string()],
+            ["Wildcard".into(), self.wildcard.to_string()],
+            [
+                "Broadcast".into(),
+                opt(self.broadcast.map(|b| b.to_string())),
+            ],
+            ["HostMin".into(), self.first_host.to_string()],
+            ["HostMax".into(), self.last_host.to_string()],
+            ["Hosts".into(), self.hosts.to_string()],
+            ["Addresses".into(), self.addresses.to_string()],
+            ["Class".into(), opt(self.class.map(|c| c.to_string()))],
+            ["Reverse zone".into(), self.reverse_zone.clone()],
+        ];
+        simple_tabulate(rows, None)
+    }
+}
+
+/* ---------------------------------- */
+
+#[inline]
+fn fam_bits(fam: IpFam) -> u8 {
+    match fam {
+        IpFam::V4 => IPV4_BITS,
+        IpFam::V6 => IPV6_BITS,
+    }
+}
+
+/// First and last usable host of a network range, see [Cidr::hosts].
+fn host_bounds(range: Range, prefix: u8) -> (u128, u128) {
+    let host_bits: u8 = fam_bits(range.fam).saturating_sub(prefix);
+    match (range.fam, host_bits) {
+        (_, 0 | 1) => (range.beg, range.end),
+        (IpFam::V4, _) => (range.beg + 1, range.end - 1),
+        (IpFam::V6, _) => (range.beg + 1, range.end),
+    }
+}
+
+#[inline]
+fn ipv4_class(first_octet: u8) -> Ipv4Class {
+    match first_octet {
+        0..=127 => Ipv4Class::A,
+        128..=191 => Ipv4Class::B,
+        192..=223 => Ipv4Class::C,
+        224..=239 => Ipv4Class::D,
+        240..=255 => Ipv4Class::E,
+    }
+}
+
+/**
+The reverse DNS zone enclosing the network: `in-addr.arpa` labels are whole
+octets and `ip6.arpa` labels nibbles, so prefixes which are not aligned to
+those get the zone of the next shorter aligned prefix (classless delegation
+per RFC 2317 is left to the caller).
+*/
+fn reverse_zone(range: Range, prefix: u8) -> String {
+    let mut labels: Vec<String> = match range.fam {
+        IpFam::V4 => {
+            let octets: [u8; 4] = (range.beg as u32).to_be_bytes();
+            octets[..(prefix / 8) as usize]
+                .iter()
+                .map(|o| o.to_string())
+                .collect()
+        }
+        IpFam::V6 => {
+            let nibbles: usize = (prefix / 4) as usize;
+            (0..nibbles)
+                .map(|i| format!("{:x}", (range.beg >> (124 - 4 * i)) & 0xf))
+                .collect()
+        }
+    };
+    labels.reverse();
+    labels.push(
+        match range.fam {
+            IpFam::V4 => V4_REVERSE,
+            IpFam::V6 => V6_REVERSE,
+        }
+        .to_string(),
+    );
+    labels.join(".")
+}
+
+/* -------------------------------------------------------------------------- */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(s: &str) -> CidrInfo {
+        s.parse::<Cidr>().unwrap().info()
+    }
+
+    fn hosts(s: &str) -> Vec<String> {
+        let cidr: Cidr = s.parse().unwrap();
+        cidr.hosts().map(|ip| ip.to_string()).collect()
+    }
+
+    #[test]
+    fn test_info_v4() {
+        let i: CidrInfo = info("192.168.1.77/26");
+        assert_eq!(i.cidr.to_string(), "192.168.1.64/26");
+        assert_eq!(i.netmask.to_string(), "255.255.255.192");
+        assert_eq!(i.wildcard.to_string(), "0.0.0.63");
+        assert_eq!(i.broadcast.unwrap().to_string(), "192.168.1.127");
+        assert_eq!(i.first_host.to_string(), "192.168.1.65");
+        assert_eq!(i.last_host.to_string(), "192.168.1.126");
+        assert_eq!((i.hosts, i.addresses), (62, 64));
+        assert_eq!(i.class, Some(Ipv4Class::C));
+        assert_eq!(i.reverse_zone, "1.168.192.in-addr.arpa");
+    }
+
+    #[test]
+    fn test_info_v4_small() {
+        let i: CidrInfo = info("10.0.0.0/31");
+        assert_eq!((i.broadcast, i.hosts), (None, 2));
+        assert_eq!(hosts("10.0.0.0/31"), ["10.0.0.0", "10.0.0.1"]);
+
+        let i: CidrInfo = info("10.0.0.7/32");
+        assert_eq!((i.broadcast, i.hosts), (None, 1));
+        assert_eq!(i.netmask.to_string(), "255.255.255.255");
+        assert_eq!(hosts("10.0.0.7/32"), ["10.0.0.7"]);
+
+        let i: CidrInfo = info("0.0.0.0/0");
+        assert_eq!((i.hosts, i.addresses), (u32::MAX as u128 - 1, 1 << 32));
+        assert_eq!(i.reverse_zone, "in-addr.arpa");
+    }
+
+    #[test]
+    fn test_info_v6() {
+        let i: CidrInfo = info("2001:db8:abcd:12::/62");
+        assert_eq!(i.broadcast, None);
+        assert_eq!(i.netmask.to_string(), "ffff:ffff:ffff:fffc::");
+        assert_eq!(i.cidr.to_string(), "2001:db8:abcd:10::/62");
+        assert_eq!(i.first_host.to_string(), "2001:db8:abcd:10::1");
+        assert_eq!(
+            i.last_host.to_string(),
+            "2001:db8:abcd:13:ffff:ffff:ffff:ffff"
+        );
+        assert_eq!(i.hosts, (1 << 66) - 1);
+        assert_eq!(i.class, None);
+        assert_eq!(i.reverse_zone, "1.0.0.d.c.b.a.8.b.d.0.1.0.0.2.ip6.arpa");
+
+        assert_eq!(
+            hosts("2001:db8::/126"),
+            ["2001:db8::1", "2001:db8::2", "2001:db8::3"]
+        );
+        assert_eq!(hosts("2001:db8::/127"), ["2001:db8::", "2001:db8::1"]);
+        assert_eq!(info("::/0").hosts, u128::MAX);
+    }
+}
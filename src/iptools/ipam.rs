@@ -0,0 +1,418 @@
+// Copyright (c) 2026 Mikko Tanner. All rights reserved.
+// Licensed under the MIT License or the Apache License, Version 2.0.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Subnet allocation (IPAM) inside a pool network.
+
+use super::{
+    collapsing::{cidr_to_range, int_to_ip, ip_to_host_cidr, range_to_cidrs, subtract_ranges},
+    prelude::*,
+    strings::*,
+    structs::{Cidr, IpFam, Range},
+    zone::ZoneId,
+    IPV4_BITS, IPV6_BITS,
+};
+use alloc::collections::BTreeMap;
+use core::{cmp::Reverse, error, fmt, iter, net::IpAddr};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum IpamError {
+    /// the block is not inside the pool (zones included)
+    OutsidePool(Cidr),
+    /// the block overlaps an existing allocation
+    Overlap(Cidr),
+    /// the block is not allocated (exactly) and cannot be released
+    NotAllocated(Cidr),
+    /// the prefix is shorter than the pool's or too long for its family
+    InvalidPrefix(u8),
+    /// no free block with the prefix is left
+    Exhausted(u8),
+}
+
+impl fmt::Display for IpamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IpamError::OutsidePool(c) => write!(f, "{ERR_IPAM_OUTSIDE}: {c}"),
+            IpamError::Overlap(c) => write!(f, "{ERR_IPAM_OVERLAP}: {c}"),
+            IpamError::NotAllocated(c) => write!(f, "{ERR_IPAM_NOT_ALLOCATED}: {c}"),
+            IpamError::InvalidPrefix(p) => write!(f, "{ERR_IPAM_PREFIX}: /{p}"),
+            IpamError::Exhausted(p) => write!(f, "{ERR_IPAM_EXHAUSTED} /{p}"),
+        }
+    }
+}
+
+impl error::Error for IpamError {}
+
+/// How [Ipam::allocate] picks among the free blocks which are large enough.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum FitStrategy {
+    /// lowest address, keeps allocations packed at the start of the pool
+    #[default]
+    FirstFit,
+    /// smallest free block, keeps large blocks intact for large requests
+    BestFit,
+}
+
+/**
+Allocator of subnets inside a pool network.
+
+Allocations are kept sorted by address and never overlap. The free space
+is everything else in the pool; it is looked at as its minimal CIDR
+decomposition, so every free block considered is naturally aligned.
+
+Serializes as the pool, the strategy and the list of allocations; the
+allocations are validated again when deserializing.
+
+```ignore
+let mut ipam = Ipam::new("10.0.0.0/16".parse()?);
+ipam.reserve("10.0.0.0/24".parse()?)?;
+let net: Cidr = ipam.allocate(26)?; // 10.0.1.0/26
+```
+*/
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "IpamState", into = "IpamState"))]
+pub struct Ipam {
+    pool: Cidr,
+    strategy: FitStrategy,
+    /// allocations by their first address
+    allocated: BTreeMap<u128, Cidr>,
+}
+
+/// Serialized form of [Ipam].
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct IpamState {
+    pool: Cidr,
+    #[serde(default)]
+    strategy: FitStrategy,
+    allocated: Vec<Cidr>,
+}
+
+impl Ipam {
+    /// An empty allocator over `pool` (host bits are ignored).
+    pub fn new(pool: Cidr) -> Self {
+        Self {
+            pool: pool.network(),
+            strategy: FitStrategy::default(),
+            allocated: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_strategy(mut self, strategy: FitStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    pub fn pool(&self) -> Cidr {
+        self.pool
+    }
+
+    pub fn strategy(&self) -> FitStrategy {
+        self.strategy
+    }
+
+    /// Allocated blocks in address order.
+    pub fn allocated(&self) -> impl Iterator<Item = &Cidr> {
+        self.allocated.values()
+    }
+
+    /// Mark `block` (host bits are ignored) as allocated.
+    pub fn reserve(&mut self, block: Cidr) -> Result<(), IpamError> {
+        let block: Cidr = block.network();
+        if !self.pool.contains(&block) {
+            return Err(IpamError::OutsidePool(block));
+        }
+        let r: Range = cidr_to_range(block);
+        // allocations are disjoint: only the last one starting before our end can overlap
+        if let Some((_, prev)) = self.allocated.range(..=r.end).next_back() {
+            if cidr_to_range(*prev).end >= r.beg {
+                return Err(IpamError::Overlap(block));
+            }
+        }
+        self.allocated.insert(r.beg, block);
+        Ok(())
+    }
+
+    /// Return an allocated block to the pool. Only whole allocations can be released.
+    pub fn release(&mut self, block: Cidr) -> Result<(), IpamError> {
+        let block: Cidr = block.network();
+        let beg: u128 = cidr_to_range(block).beg;
+        match self.allocated.get(&beg) {
+            Some(c) if *c == block => {
+                self.allocated.remove(&beg);
+                Ok(())
+            }
+            _ => Err(IpamError::NotAllocated(block)),
+        }
+    }
+
+    /// The free block [Ipam::allocate] would return, without allocating it.
+    pub fn find(&self, prefix: u8) -> Result<Cidr, IpamError> {
+        self.find_from(prefix, cidr_to_range(self.pool).beg)
+    }
+
+    /// Allocate a free `/prefix` block according to the [FitStrategy].
+    pub fn allocate(&mut self, prefix: u8) -> Result<Cidr, IpamError> {
+        let block: Cidr = self.find(prefix)?;
+        self.reserve(block)?;
+        Ok(block)
+    }
+
+    /**
+    Allocate a free `/prefix` block starting at or after `after`, f.ex. to
+    keep the start of the pool for something else. The [FitStrategy]
+    applies to the free space from `after` on.
+    */
+    pub fn allocate_after(&mut self, prefix: u8, after: IpAddr) -> Result<Cidr, IpamError> {
+        let (pool, host): (Range, Range) = (
+            cidr_to_range(self.pool),
+            cidr_to_range(ip_to_host_cidr(after)),
+        );
+        if host.fam != pool.fam {
+            return Err(IpamError::OutsidePool(ip_to_host_cidr(after)));
+        }
+        let block: Cidr = self.find_from(prefix, host.beg.max(pool.beg))?;
+        self.reserve(block)?;
+        Ok(block)
+    }
+
+    /// All free space, as the minimal list of CIDRs.
+    pub fn free(&self) -> Vec<Cidr> {
+        self.free_ranges()
+            .into_iter()
+            .flat_map(range_to_cidrs)
+            .collect()
+    }
+
+    /**
+    All free `/prefix` blocks in address order, f.ex. "all free /24s".
+
+    Lazy, as there may be a lot of them (think free /64s in an IPv6 /32).
+    Empty if `prefix` is not valid for the pool.
+    */
+    pub fn free_blocks(&self, prefix: u8) -> impl Iterator<Item = Cidr> {
+        let valid: bool = self.check_prefix(prefix).is_ok();
+        let bits: u8 = self.bits();
+        let fam: IpFam = cidr_to_range(self.pool).fam;
+        let zone: Option<ZoneId> = self.pool.zone;
+        self.free()
+            .into_iter()
+            .filter(move |c| valid && c.prefix <= prefix)
+            .flat_map(move |c| {
+                let r: Range = cidr_to_range(c);
+                // None: a /0 in IPv6, which is one block anyway
+                let size: Option<u128> = 1u128.checked_shl((bits - prefix) as u32);
+                iter::successors(Some(r.beg), move |beg| {
+                    size.and_then(|s| beg.checked_add(s))
+                        .filter(|n| *n <= r.end)
+                })
+                .map(move |beg| Cidr {
+                    addr: int_to_ip(fam, beg),
+                    prefix,
+                    zone,
+                })
+            })
+    }
+
+    /// The largest free aligned block, the lowest one if there are several.
+    pub fn largest_free(&self) -> Option<Cidr> {
+        // min_by_key keeps the first of equal elements, i.e. the lowest address
+        self.free().into_iter().min_by_key(|c| c.prefix)
+    }
+
+    /* ---------------------------------- */
+
+    #[inline]
+    fn bits(&self) -> u8 {
+        match self.pool.addr {
+            IpAddr::V4(_) => IPV4_BITS,
+            IpAddr::V6(_) => IPV6_BITS,
+        }
+    }
+
+    fn check_prefix(&self, prefix: u8) -> Result<(), IpamError> {
+        match prefix >= self.pool.prefix && prefix <= self.bits() {
+            true => Ok(()),
+            false => Err(IpamError::InvalidPrefix(prefix)),
+        }
+    }
+
+    /// Free space as sorted, disjoint ranges.
+    fn free_ranges(&self) -> Vec<Range> {
+        let used: Vec<Range> = self.allocated.values().map(|c| cidr_to_range(*c)).collect();
+        subtract_ranges(&[cidr_to_range(self.pool)], &used)
+    }
+
+    /**
+    Pick a free block with the strategy among the free space from `from` on.
+    Every aligned block in a free range lies within exactly one block of the
+    range's CIDR decomposition, so it suffices to look at those.
+    */
+    fn find_from(&self, prefix: u8, from: u128) -> Result<Cidr, IpamError> {
+        self.check_prefix(prefix)?;
+        let mut candidates = self
+            .free_ranges()
+            .into_iter()
+            .filter(|r| r.end >= from)
+            .map(|r| Range {
+                beg: r.beg.max(from),
+                ..r
+            })
+            .flat_map(range_to_cidrs)
+            .filter(|c| c.prefix <= prefix);
+        let block: Option<Cidr> = match self.strategy {
+            FitStrategy::FirstFit => candidates.next(),
+            // smallest block (longest prefix) first, then the lowest address
+            FitStrategy::BestFit => candidates.min_by_key(|c| (Reverse(c.prefix), c.addr)),
+        };
+        block
+            .map(|c| Cidr { prefix, ..c })
+            .ok_or(IpamError::Exhausted(prefix))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Ipam> for IpamState {
+    fn from(ipam: Ipam) -> Self {
+        Self {
+            pool: ipam.pool,
+            strategy: ipam.strategy,
+            allocated: ipam.allocated.into_values().collect(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<IpamState> for Ipam {
+    type Error = IpamError;
+
+    fn try_from(state: IpamState) -> Result<Self, Self::Error> {
+        let mut ipam: Ipam = Ipam::new(state.pool).with_strategy(state.strategy);
+        for block in state.allocated {
+            ipam.reserve(block)?;
+        }
+        Ok(ipam)
+    }
+}
+
+/* -------------------------------------------------------------------------- */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POOL: &str = "10.0.0.0/16";
+    const USED: [&str; 3] = ["10.0.0.0/24", "10.0.1.0/26", "10.0.2.128/25"];
+
+    fn cidr(s: &str) -> Cidr {
+        s.parse().unwrap()
+    }
+
+    fn ipam(strategy: FitStrategy) -> Ipam {
+        let mut ipam: Ipam = Ipam::new(cidr(POOL)).with_strategy(strategy);
+        for block in USED {
+            ipam.reserve(cidr(block)).unwrap();
+        }
+        ipam
+    }
+
+    #[test]
+    fn test_first_and_best_fit() {
+        let mut first: Ipam = ipam(FitStrategy::FirstFit);
+        assert_eq!(first.allocate(26).unwrap(), cidr("10.0.1.64/26"));
+        assert_eq!(first.allocate(24).unwrap(), cidr("10.0.3.0/24"));
+
+        // best fit takes the /25 hole in 10.0.2.0/24 rather than splitting 10.0.1.128/25
+        let mut best: Ipam = ipam(FitStrategy::BestFit);
+        best.reserve(cidr("10.0.1.64/26")).unwrap();
+        assert_eq!(best.find(25).unwrap(), cidr("10.0.1.128/25"));
+        assert_eq!(best.allocate(26).unwrap(), cidr("10.0.1.128/26"));
+        assert_eq!(best.allocate(25).unwrap(), cidr("10.0.2.0/25"));
+
+        assert_eq!(
+            first
+                .allocate_after(27, "10.0.4.0".parse().unwrap())
+                .unwrap(),
+            cidr("10.0.4.0/27")
+        );
+        assert_eq!(
+            first
+                .allocate_after(27, "10.0.4.1".parse().unwrap())
+                .unwrap(),
+            cidr("10.0.4.32/27")
+        );
+        assert_eq!(first.allocate(8), Err(IpamError::InvalidPrefix(8)));
+        assert_eq!(first.allocate(33), Err(IpamError::InvalidPrefix(33)));
+        assert_eq!(first.allocate(16), Err(IpamError::Exhausted(16)));
+    }
+
+    #[test]
+    fn test_free_space() {
+        let ipam: Ipam = ipam(FitStrategy::FirstFit);
+        let free24: Vec<Cidr> = ipam.free_blocks(24).take(3).collect();
+        assert_eq!(
+            free24,
+            [
+                cidr("10.0.3.0/24"),
+                cidr("10.0.4.0/24"),
+                cidr("10.0.5.0/24")
+            ]
+        );
+        assert_eq!(ipam.free_blocks(24).count(), 256 - 3);
+        assert_eq!(
+            ipam.free_blocks(26).take(2).last(),
+            Some(cidr("10.0.1.128/26"))
+        );
+        assert_eq!(ipam.largest_free(), Some(cidr("10.0.128.0/17")));
+        assert_eq!(ipam.free()[0], cidr("10.0.1.64/26"));
+
+        let v6: Ipam = Ipam::new(cidr("::/0"));
+        assert_eq!(v6.free_blocks(0).collect::<Vec<Cidr>>(), [cidr("::/0")]);
+        assert_eq!(v6.free_blocks(1).count(), 2);
+    }
+
+    #[test]
+    fn test_reserve_release_state() {
+        let mut ipam: Ipam = ipam(FitStrategy::BestFit);
+        assert_eq!(
+            ipam.reserve(cidr("10.0.0.128/25")),
+            Err(IpamError::Overlap(cidr("10.0.0.128/25")))
+        );
+        assert_eq!(
+            ipam.reserve(cidr("10.0.2.0/23")),
+            Err(IpamError::Overlap(cidr("10.0.2.0/23")))
+        );
+        assert!(matches!(
+            ipam.reserve(cidr("10.1.0.0/24")),
+            Err(IpamError::OutsidePool(_))
+        ));
+        assert!(matches!(
+            ipam.release(cidr("10.0.0.0/25")),
+            Err(IpamError::NotAllocated(_))
+        ));
+
+        #[cfg(feature = "serde")]
+        {
+            let state: IpamState = ipam.clone().into();
+            assert_eq!(state.allocated.len(), USED.len());
+            assert_eq!(Ipam::try_from(state).unwrap(), ipam);
+        }
+
+        ipam.release(cidr("10.0.0.5/24")).unwrap();
+        assert_eq!(ipam.allocated().next(), Some(&cidr("10.0.1.0/26")));
+
+        #[cfg(feature = "serde")]
+        {
+            let bad: IpamState = IpamState {
+                pool: cidr(POOL),
+                strategy: FitStrategy::FirstFit,
+                allocated: vec![cidr("10.0.0.0/24"), cidr("10.0.0.0/25")],
+            };
+            assert!(Ipam::try_from(bad).is_err());
+        }
+    }
+}
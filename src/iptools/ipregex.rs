@@ -0,0 +1,395 @@
+// Copyright (c) 2026 Mikko Tanner. All rights reserved.
+// Licensed under the MIT License or the Apache License, Version 2.0.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+/*!
+Regular expressions which match exactly the textual addresses in a CIDR set.
+
+The patterns only use syntax shared by the [regex] crate, `grep -E` and
+ripgrep: plain groups, bracket classes and bounded repeats.
+
+IPv4 addresses are matched in dotted-decimal form without leading zeros.
+IPv6 addresses are matched in lowercase without leading zeros in the
+hextets, with `::` compressing any run of zero hextets (or none). That
+includes the canonical RFC 5952 form, but not embedded dotted IPv4
+(`::ffff:10.0.0.1`). Zones are ignored.
+*/
+
+use super::{
+    collapsing::sorted_merged,
+    structs::{Cidr, IpFam},
+};
+use regex::{Regex, RegexBuilder};
+
+/// Matches nothing: `^` cannot follow a character.
+static NEVER: &str = "a^";
+/// Compiled size limit for [compile_regex], generous for large sets.
+const REGEX_SIZE_LIMIT: usize = 256 << 20;
+
+/// What may surround an address matched by [cidrs_to_regex].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum RegexBoundary {
+    /// nothing: the pattern also matches inside longer addresses
+    None,
+    /// the address is the whole line: `^...$`
+    Line,
+    /**
+    the address is not part of a longer one, f.ex. in log lines. The match
+    includes one delimiting character on each side, where present, as the
+    portable syntax has no lookaround. A trailing `.` or `:` is fine if no
+    address character follows it.
+    */
+    #[default]
+    Text,
+}
+
+/**
+Build a regular expression matching exactly the textual addresses in
+`cidrs`, see the [module docs](self) for the accepted notations.
+
+```ignore
+let re: String = cidrs_to_regex(&["10.0.0.0/22".parse().unwrap()], RegexBoundary::Line);
+// ^10\.0\.[0-3]\.(25[0-5]|2[0-4][0-9]|1[0-9]{2}|[1-9][0-9]|[0-9])$
+```
+*/
+pub fn cidrs_to_regex(cidrs: &[Cidr], boundary: RegexBoundary) -> String {
+    let unzoned: Vec<Cidr> = cidrs.iter().map(|c| c.without_zone()).collect();
+    let (mut v4, mut v6): (Vec<String>, Vec<String>) = (Vec::new(), Vec::new());
+    for r in sorted_merged(&unzoned) {
+        match r.fam {
+            IpFam::V4 => {
+                let (beg, end): ([u32; 4], [u32; 4]) = (digits(r.beg), digits(r.end));
+                for piece in pieces(&beg, &end, u8::MAX as u32) {
+                    let octets: Vec<String> =
+                        piece.iter().map(|(a, b)| num_regex(*a, *b, 10)).collect();
+                    v4.push(octets.join(r"\."));
+                }
+            }
+            IpFam::V6 => {
+                let (beg, end): ([u32; 8], [u32; 8]) = (digits(r.beg), digits(r.end));
+                for piece in pieces(&beg, &end, u16::MAX as u32) {
+                    v6.extend(compressed_forms(&piece));
+                }
+            }
+        }
+    }
+
+    let (v4, v6): (Option<String>, Option<String>) = (alternation(&v4), alternation(&v6));
+    match boundary {
+        RegexBoundary::None => {
+            alternation(&[v4, v6].into_iter().flatten().collect::<Vec<String>>())
+        }
+        RegexBoundary::Line => {
+            alternation(&[v4, v6].into_iter().flatten().collect::<Vec<String>>())
+                .map(|p| format!("^{p}$"))
+        }
+        RegexBoundary::Text => {
+            let v4 = v4.map(|p| format!(r"(^|[^0-9.]){p}($|[^0-9.]|\.($|[^0-9]))"));
+            let v6 = v6.map(|p| format!("(^|[^0-9a-f:]){p}($|[^0-9a-f:.]|:($|[^0-9a-f:]))"));
+            alternation(&[v4, v6].into_iter().flatten().collect::<Vec<String>>())
+        }
+    }
+    .unwrap_or_else(|| NEVER.to_string())
+}
+
+/// [cidrs_to_regex], compiled with the [regex] crate.
+pub fn compile_regex(cidrs: &[Cidr], boundary: RegexBoundary) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(&cidrs_to_regex(cidrs, boundary))
+        .size_limit(REGEX_SIZE_LIMIT)
+        .build()
+}
+
+/* ---------------------------------- */
+
+/// Split an address integer into its `N` octets or hextets, most significant first.
+fn digits<const N: usize>(v: u128) -> [u32; N] {
+    let bits: usize = if N == 4 { 8 } else { 16 };
+    std::array::from_fn(|i| ((v >> (bits * (N - 1 - i))) & ((1 << bits) - 1)) as u32)
+}
+
+/**
+Split the inclusive range `beg..=end` (per-position digits up to `max`)
+into pieces of the form: fixed leading positions, one ranged position,
+and any value in the rest. Each piece is given as per-position ranges.
+*/
+fn pieces(beg: &[u32], end: &[u32], max: u32) -> Vec<Vec<(u32, u32)>> {
+    let mut out: Vec<Vec<(u32, u32)>> = Vec::new();
+    split(beg, end, max, &mut Vec::new(), &mut out);
+    out
+}
+
+fn split(
+    beg: &[u32],
+    end: &[u32],
+    max: u32,
+    prefix: &mut Vec<(u32, u32)>,
+    out: &mut Vec<Vec<(u32, u32)>>,
+) {
+    let Some((&a, beg_rest)) = beg.split_first() else {
+        out.push(prefix.clone());
+        return;
+    };
+    let (&b, end_rest) = end.split_first().expect("same length");
+    let (from_min, to_max): (bool, bool) = (
+        beg_rest.iter().all(|d| *d == 0),
+        end_rest.iter().all(|d| *d == max),
+    );
+    let full = |prefix: &Vec<(u32, u32)>, lo: u32, hi: u32| -> Vec<(u32, u32)> {
+        let mut piece: Vec<(u32, u32)> = prefix.clone();
+        piece.push((lo, hi));
+        piece.extend(beg_rest.iter().map(|_| (0, max)));
+        piece
+    };
+
+    if a == b {
+        prefix.push((a, a));
+        split(beg_rest, end_rest, max, prefix, out);
+        prefix.pop();
+        return;
+    }
+    let (mut lo, mut hi): (u32, u32) = (a, b);
+    if !from_min {
+        prefix.push((a, a));
+        split(beg_rest, &vec![max; end_rest.len()], max, prefix, out);
+        prefix.pop();
+        lo += 1;
+    }
+    if !to_max {
+        hi -= 1;
+    }
+    if lo <= hi {
+        out.push(full(prefix, lo, hi));
+    }
+    if !to_max {
+        prefix.push((b, b));
+        split(&vec![0; beg_rest.len()], end_rest, max, prefix, out);
+        prefix.pop();
+    }
+}
+
+/// All the textual forms of an IPv6 piece: uncompressed, and `::` over each run of positions which may be zero.
+fn compressed_forms(piece: &[(u32, u32)]) -> Vec<String> {
+    let hextets: Vec<String> = piece.iter().map(|(a, b)| num_regex(*a, *b, 16)).collect();
+    let mut out: Vec<String> = vec![hextets.join(":")];
+    for i in 0..piece.len() {
+        for j in i + 1..=piece.len() {
+            if piece[j - 1].0 != 0 {
+                break;
+            }
+            out.push(format!(
+                "{}::{}",
+                hextets[..i].join(":"),
+                hextets[j..].join(":")
+            ));
+        }
+    }
+    out
+}
+
+/**
+Regex for the numbers `lo..=hi` in base 10 or 16 (lowercase), without
+leading zeros.
+*/
+fn num_regex(lo: u32, hi: u32, radix: u32) -> String {
+    let mut alts: Vec<String> = Vec::new();
+    let mut width: u32 = 1;
+    loop {
+        let min: u32 = if width == 1 { 0 } else { radix.pow(width - 1) };
+        let max: u32 = radix.pow(width) - 1;
+        if min > hi {
+            break;
+        }
+        let (a, b): (u32, u32) = (lo.max(min), hi.min(max));
+        if a <= b {
+            same_width(a, b, width, radix, String::new(), &mut alts);
+        }
+        width += 1;
+    }
+    // longest first, so that unanchored matches prefer whole numbers
+    alts.reverse();
+    alternation(&alts).expect("lo <= hi")
+}
+
+/// Patterns for `lo..=hi`, all with `width` digits (inner digits may be zero).
+fn same_width(lo: u32, hi: u32, width: u32, radix: u32, prefix: String, out: &mut Vec<String>) {
+    if width == 1 {
+        out.push(format!("{prefix}{}", class(lo, hi)));
+        return;
+    }
+    let p: u32 = radix.pow(width - 1);
+    let (mut a, mut b): (u32, u32) = (lo / p, hi / p);
+    if a == b {
+        same_width(
+            lo % p,
+            hi % p,
+            width - 1,
+            radix,
+            format!("{prefix}{}", class(a, a)),
+            out,
+        );
+        return;
+    }
+    let head: bool = !lo.is_multiple_of(p);
+    let tail: bool = hi % p != p - 1;
+    if head {
+        same_width(
+            lo % p,
+            p - 1,
+            width - 1,
+            radix,
+            format!("{prefix}{}", class(a, a)),
+            out,
+        );
+        a += 1;
+    }
+    if tail {
+        b -= 1;
+    }
+    if a <= b {
+        let any: String = class(0, radix - 1);
+        let rest: String = match width - 1 {
+            1 => any,
+            n => format!("{any}{{{n}}}"),
+        };
+        out.push(format!("{prefix}{}{rest}", class(a, b)));
+    }
+    if tail {
+        same_width(
+            0,
+            hi % p,
+            width - 1,
+            radix,
+            format!("{prefix}{}", class(b + 1, b + 1)),
+            out,
+        );
+    }
+}
+
+/// A single digit, or a bracket class for `lo..=hi`.
+fn class(lo: u32, hi: u32) -> String {
+    let digit = |d: u32| char::from_digit(d, 16).expect("digit");
+    if lo == hi {
+        return digit(lo).to_string();
+    }
+    let mut out: String = String::from("[");
+    for (a, b) in [(lo, hi.min(9)), (lo.max(10), hi)] {
+        match b.checked_sub(a) {
+            Some(0) => out.push(digit(a)),
+            Some(_) => out.extend([digit(a), '-', digit(b)]),
+            None => {}
+        }
+    }
+    out.push(']');
+    out
+}
+
+/// `(a|b|...)`, a single alternative as is, or None if there are none.
+fn alternation<S: AsRef<str>>(alts: &[S]) -> Option<String> {
+    match alts {
+        [] => None,
+        [one] => Some(one.as_ref().to_string()),
+        _ => Some(format!(
+            "({})",
+            alts.iter()
+                .map(|a| a.as_ref())
+                .collect::<Vec<&str>>()
+                .join("|")
+        )),
+    }
+}
+
+/* -------------------------------------------------------------------------- */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+    #[test]
+    fn test_num_regex() {
+        for (lo, hi, radix) in [
+            (0, 255, 10),
+            (7, 193, 10),
+            (0, 0xffff, 16),
+            (0x9, 0xa0f, 16),
+        ] {
+            let re: Regex = Regex::new(&format!("^{}$", num_regex(lo, hi, radix))).unwrap();
+            for v in 0..=(if radix == 10 { 999 } else { 0xffff }) {
+                let s: String = match radix {
+                    10 => v.to_string(),
+                    _ => format!("{v:x}"),
+                };
+                assert_eq!(
+                    re.is_match(&s),
+                    (lo..=hi).contains(&v),
+                    "{s} in {lo}..={hi}"
+                );
+            }
+            assert!(!re.is_match("01"));
+        }
+    }
+
+    #[test]
+    fn test_v4_exact() {
+        let cidrs: Vec<Cidr> = ["10.0.0.0/22", "192.168.1.7/32", "172.16.5.128/25"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        let re: Regex = compile_regex(&cidrs, RegexBoundary::Line).unwrap();
+        for base in [0x0a00_0000u32, 0xc0a8_0100, 0xac10_0500] {
+            for v in base - 300..base + 1324 {
+                let ip: IpAddr = Ipv4Addr::from(v).into();
+                let inside: bool = cidrs.iter().any(|c| c.contains_ip(ip));
+                assert_eq!(re.is_match(&ip.to_string()), inside, "{ip}");
+            }
+        }
+
+        let text: Regex = compile_regex(&cidrs, RegexBoundary::Text).unwrap();
+        for (line, hit) in [
+            ("deny src=10.0.3.255 dst=8.8.8.8", true),
+            ("host 192.168.1.7.", true),
+            ("host 192.168.1.70", false),
+            ("oid 1.10.0.0.1", false),
+            ("10.0.0.1:443", true),
+            ("210.0.0.1", false),
+        ] {
+            assert_eq!(text.is_match(line), hit, "{line}");
+        }
+        assert!(!compile_regex(&[], RegexBoundary::None)
+            .unwrap()
+            .is_match("10.0.0.1"));
+    }
+
+    #[test]
+    fn test_v6_forms() {
+        let cidrs: Vec<Cidr> = ["2001:db8::/120", "2001:db8:0:1::ff00/120", "fe80::/10"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        let re: Regex = compile_regex(&cidrs, RegexBoundary::Line).unwrap();
+        let base: u128 = u128::from("2001:db8::".parse::<Ipv6Addr>().unwrap());
+        for v in
+            (base - 3..base + 300).chain(base + (1 << 64) + 0xfe00..base + (1 << 64) + 0x1_0003)
+        {
+            let ip: IpAddr = Ipv6Addr::from(v).into();
+            let inside: bool = cidrs.iter().any(|c| c.contains_ip(ip));
+            assert_eq!(re.is_match(&ip.to_string()), inside, "{ip}");
+        }
+        for (s, hit) in [
+            ("2001:db8:0:0:0:0:0:ff", true),
+            ("2001:db8::0:ff", true),
+            ("2001:db8:0:1:0:0:0:ff00", true),
+            ("fe80::1", true),
+            ("febf:ffff::", true),
+            ("fec0::", false),
+            ("2001:DB8::1", false),
+        ] {
+            assert_eq!(re.is_match(s), hit, "{s}");
+        }
+
+        let text: Regex = compile_regex(&cidrs, RegexBoundary::Text).unwrap();
+        assert!(text.is_match("from [fe80::1%eth0]:22"));
+        assert!(text.is_match("addr 2001:db8::5: refused"));
+        assert!(!text.is_match("addr 2001:db8::5:1"));
+    }
+}
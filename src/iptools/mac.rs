@@ -0,0 +1,316 @@
+// Copyright (c) 2026 Mikko Tanner. All rights reserved.
+// Licensed under the MIT License or the Apache License, Version 2.0.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! MAC (EUI-48) addresses and their modified EUI-64 / SLAAC IPv6 mapping.
+
+use super::{prelude::*, strings::*, structs::Cidr};
+use core::{
+    error, fmt,
+    net::{IpAddr, Ipv6Addr},
+    str::FromStr,
+};
+#[cfg(feature = "serde")]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// SLAAC interface identifiers are always 64 bits (RFC 4291, section 2.5.1).
+const SLAAC_PREFIX: u8 = 64;
+/// Universal/local bit of the first octet; inverted in modified EUI-64.
+const UL_BIT: u8 = 0x02;
+/// Individual/group bit of the first octet.
+const IG_BIT: u8 = 0x01;
+/// Filler inserted in the middle of a MAC to make an EUI-64.
+const EUI64_FILL: [u8; 2] = [0xff, 0xfe];
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MacError {
+    /// not a MAC address in any of the supported notations
+    Invalid(String),
+    /// SLAAC needs an IPv6 /64 network
+    Prefix(Cidr),
+}
+
+impl fmt::Display for MacError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MacError::Invalid(s) => write!(f, "{ERR_MAC_INVALID}: '{s}'"),
+            MacError::Prefix(c) => write!(f, "{ERR_MAC_PREFIX} {c}"),
+        }
+    }
+}
+
+impl error::Error for MacError {}
+
+/// Notation for [MacAddr::format]. All of them are accepted by [MacAddr::from_str].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum MacFormat {
+    /// `00:1a:2b:3c:4d:5e`
+    #[default]
+    Colon,
+    /// `00-1a-2b-3c-4d-5e`
+    Dash,
+    /// `001a.2b3c.4d5e`
+    Cisco,
+    /// `001a2b3c4d5e`
+    Bare,
+}
+
+/// 48-bit MAC address.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct MacAddr([u8; 6]);
+
+impl MacAddr {
+    pub const fn new(octets: [u8; 6]) -> Self {
+        Self(octets)
+    }
+
+    pub const fn octets(&self) -> [u8; 6] {
+        self.0
+    }
+
+    /// Group address (including broadcast).
+    pub fn is_multicast(&self) -> bool {
+        self.0[0] & IG_BIT != 0
+    }
+
+    pub fn is_unicast(&self) -> bool {
+        !self.is_multicast()
+    }
+
+    pub fn is_broadcast(&self) -> bool {
+        self.0 == [0xff; 6]
+    }
+
+    /// Locally administered, f.ex. randomized or assigned by a hypervisor.
+    pub fn is_local(&self) -> bool {
+        self.0[0] & UL_BIT != 0
+    }
+
+    /// Universally administered, i.e. assigned by the vendor (OUI).
+    pub fn is_universal(&self) -> bool {
+        !self.is_local()
+    }
+
+    /// Format the address in the given notation (lowercase hex).
+    pub fn format(&self, fmt: MacFormat) -> String {
+        let o: [u8; 6] = self.0;
+        match fmt {
+            MacFormat::Colon | MacFormat::Dash => {
+                let sep: &str = if fmt == MacFormat::Colon { ":" } else { "-" };
+                o.iter()
+                    .map(|b| format!("{b:02x}"))
+                    .collect::<Vec<String>>()
+                    .join(sep)
+            }
+            MacFormat::Cisco => format!(
+                "{:02x}{:02x}.{:02x}{:02x}.{:02x}{:02x}",
+                o[0], o[1], o[2], o[3], o[4], o[5]
+            ),
+            MacFormat::Bare => o.iter().map(|b| format!("{b:02x}")).collect(),
+        }
+    }
+
+    /**
+    The modified EUI-64 interface identifier of this MAC (RFC 4291,
+    appendix A): `ff:fe` inserted in the middle and the universal/local bit
+    inverted.
+    */
+    pub fn to_eui64(&self) -> [u8; 8] {
+        let o: [u8; 6] = self.0;
+        [
+            o[0] ^ UL_BIT,
+            o[1],
+            o[2],
+            EUI64_FILL[0],
+            EUI64_FILL[1],
+            o[3],
+            o[4],
+            o[5],
+        ]
+    }
+
+    /// Inverse of [MacAddr::to_eui64]. None if `iid` was not derived from a MAC.
+    pub fn from_eui64(iid: [u8; 8]) -> Option<Self> {
+        if iid[3..5] != EUI64_FILL {
+            return None;
+        }
+        Some(Self([
+            iid[0] ^ UL_BIT,
+            iid[1],
+            iid[2],
+            iid[5],
+            iid[6],
+            iid[7],
+        ]))
+    }
+
+    /**
+    The SLAAC (EUI-64) address of this MAC inside `network`, which must be
+    an IPv6 /64. Host bits of `network` are ignored, and so is its zone: for
+    link-local networks the address is only meaningful in that zone.
+    */
+    pub fn slaac(&self, network: &Cidr) -> Result<Ipv6Addr, MacError> {
+        match network.network().addr {
+            IpAddr::V6(net) if network.prefix == SLAAC_PREFIX => {
+                let iid: u64 = u64::from_be_bytes(self.to_eui64());
+                Ok(Ipv6Addr::from(u128::from(net) | iid as u128))
+            }
+            _ => Err(MacError::Prefix(*network)),
+        }
+    }
+
+    /// The MAC embedded in an EUI-64 based (SLAAC) IPv6 address, if it is one.
+    pub fn from_ipv6(ip: Ipv6Addr) -> Option<Self> {
+        let iid: u64 = u128::from(ip) as u64;
+        Self::from_eui64(iid.to_be_bytes())
+    }
+}
+
+impl fmt::Display for MacAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.format(MacFormat::Colon))
+    }
+}
+
+impl FromStr for MacAddr {
+    type Err = MacError;
+
+    /// Parse any of the [MacFormat] notations, case-insensitively.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s: &str = s.trim();
+        let invalid = || MacError::Invalid(s.to_string());
+
+        // (separator, number of groups, hex digits per group)
+        let (sep, groups, digits): (Option<char>, usize, usize) = if s.contains(':') {
+            (Some(':'), 6, 2)
+        } else if s.contains('-') {
+            (Some('-'), 6, 2)
+        } else if s.contains('.') {
+            (Some('.'), 3, 4)
+        } else {
+            (None, 1, 12)
+        };
+        let parts: Vec<&str> = match sep {
+            Some(c) => s.split(c).collect(),
+            None => vec![s],
+        };
+        let valid: bool = parts.len() == groups
+            && parts
+                .iter()
+                .all(|p| p.len() == digits && p.bytes().all(|b| b.is_ascii_hexdigit()));
+        if !valid {
+            return Err(invalid());
+        }
+
+        let hex: String = parts.concat();
+        let mut octets: [u8; 6] = [0; 6];
+        for (i, o) in octets.iter_mut().enumerate() {
+            *o = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).map_err(|_| invalid())?;
+        }
+        Ok(Self(octets))
+    }
+}
+
+impl From<[u8; 6]> for MacAddr {
+    fn from(octets: [u8; 6]) -> Self {
+        Self(octets)
+    }
+}
+
+impl From<MacAddr> for [u8; 6] {
+    fn from(mac: MacAddr) -> Self {
+        mac.0
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for MacAddr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for MacAddr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s: String = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+/* -------------------------------------------------------------------------- */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAC: &str = "00:1a:2b:3c:4d:5e";
+    const NETWORK: &str = "2001:db8:1:2::/64";
+    const SLAAC: &str = "2001:db8:1:2:21a:2bff:fe3c:4d5e";
+
+    #[test]
+    fn test_parse_and_format() {
+        let mac: MacAddr = MAC.parse().unwrap();
+        for (s, fmt) in [
+            (MAC, MacFormat::Colon),
+            ("00-1a-2b-3c-4d-5e", MacFormat::Dash),
+            ("001a.2b3c.4d5e", MacFormat::Cisco),
+            ("001a2b3c4d5e", MacFormat::Bare),
+        ] {
+            assert_eq!(s.to_uppercase().parse::<MacAddr>().unwrap(), mac, "{s}");
+            assert_eq!(mac.format(fmt), s);
+        }
+        assert_eq!(mac.to_string(), MAC);
+
+        for bad in [
+            "",
+            "00:1a:2b:3c:4d",
+            "00:1a:2b:3c:4d:5e:6f",
+            "0:1a:2b:3c:4d:5e",
+            "001a.2b3c",
+            "00:1a-2b:3c:4d:5e",
+            "00:1a:2b:3c:4d:5g",
+        ] {
+            assert!(bad.parse::<MacAddr>().is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_flags() {
+        let mac: MacAddr = MAC.parse().unwrap();
+        assert!(mac.is_unicast() && mac.is_universal() && !mac.is_broadcast());
+
+        let local: MacAddr = "02:42:ac:11:00:02".parse().unwrap();
+        assert!(local.is_local() && local.is_unicast());
+
+        let mcast: MacAddr = "33:33:00:00:00:01".parse().unwrap();
+        assert!(mcast.is_multicast() && !mcast.is_broadcast());
+        assert!(MacAddr::new([0xff; 6]).is_broadcast());
+    }
+
+    #[test]
+    fn test_eui64_slaac() {
+        let mac: MacAddr = MAC.parse().unwrap();
+        assert_eq!(
+            mac.to_eui64(),
+            [0x02, 0x1a, 0x2b, 0xff, 0xfe, 0x3c, 0x4d, 0x5e]
+        );
+        assert_eq!(MacAddr::from_eui64(mac.to_eui64()), Some(mac));
+
+        let ip: Ipv6Addr = mac.slaac(&NETWORK.parse().unwrap()).unwrap();
+        assert_eq!(ip.to_string(), SLAAC);
+        assert_eq!(MacAddr::from_ipv6(ip), Some(mac));
+        // host bits of the network do not matter
+        assert_eq!(
+            mac.slaac(&"2001:db8:1:2::99/64".parse().unwrap()).unwrap(),
+            ip
+        );
+
+        assert_eq!(MacAddr::from_ipv6("2001:db8::1".parse().unwrap()), None);
+        assert!(matches!(
+            mac.slaac(&"2001:db8::/48".parse().unwrap()),
+            Err(MacError::Prefix(_))
+        ));
+        assert!(mac.slaac(&"10.0.0.0/8".parse().unwrap()).is_err());
+    }
+}
@@ -2,23 +2,98 @@
 // Licensed under the MIT License or the Apache License, Version 2.0.
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-//! IP address and/or CIDR parsing/collapsing into minimal representations.
+/*!
+IP address and/or CIDR parsing/collapsing into minimal representations.
+
+Without the `std` feature this module only needs `alloc` and uses the
+[core::net] address types. The parts which need more are gated on their own:
+external collapsing and `write_ipset` need `std`, cloud range parsing needs
+`cloud`, text extraction and regex generation need `regex`, and the
+`AddressMap` and `to_table` renderers need `tabulate`.
+*/
 
 mod addresses;
+#[cfg(feature = "tabulate")]
+mod addrmap;
+mod anonymize;
+mod binary;
+mod bitmap;
+mod classify;
+#[cfg(feature = "cloud")]
+mod cloud;
 mod collapsing;
+mod expr;
+#[cfg(feature = "std")]
+mod external;
+#[cfg(feature = "regex")]
+mod extract;
+mod info;
+mod ipam;
+#[cfg(feature = "regex")]
+mod ipregex;
+mod mac;
+mod overlap;
+mod spec;
+mod stats;
 mod strings;
 mod structs;
+mod tagged;
+mod vlsm;
+mod zone;
 
-use std::{
+use core::{
     error, fmt,
     net::{AddrParseError, IpAddr},
     num::ParseIntError,
 };
+use prelude::*;
 use strings::*;
 
 pub use addresses::*;
+#[cfg(feature = "tabulate")]
+pub use addrmap::{AddressMap, CellState, MapCell, MapError, MAP_MAX_CELLS};
+pub use anonymize::{PrefixAnonymizer, Truncation};
+#[cfg(feature = "std")]
+pub use binary::write_ipset;
+pub use binary::{encode_ipset, ipset_to_text, text_to_ipset, IpSetError, IpSetView};
+pub use bitmap::Ipv4Bitmap;
+pub use classify::{classify_ip, AddressKind};
+#[cfg(feature = "cloud")]
+pub use cloud::{
+    parse_aws, parse_azure, parse_cloudflare, parse_gcp, CloudError, CloudFilter, CloudProvider,
+    CloudRange,
+};
 pub use collapsing::*;
-pub use structs::{Cidr, IpFam, IpRange};
+pub use expr::{eval_expr, Expr, ExprError};
+#[cfg(feature = "std")]
+pub use external::{CollapseStream, ExternalCollapser, ExternalError};
+#[cfg(feature = "regex")]
+pub use extract::{extract_addresses, replace_ips, Extracted};
+pub use info::{CidrInfo, Ipv4Class};
+pub use ipam::{FitStrategy, Ipam, IpamError};
+#[cfg(feature = "regex")]
+pub use ipregex::{cidrs_to_regex, compile_regex, RegexBoundary};
+pub use mac::{MacAddr, MacError, MacFormat};
+pub use overlap::{find_overlaps, Overlap, OverlapEntry, OverlapReport};
+pub use spec::AddressSpec;
+pub use stats::{cidr_stats, CidrStats, FamilyStats};
+pub use structs::{Cidr, IpFam, IpRange, RangeFormat};
+pub use tagged::{collapse_tagged, TaggedCidr};
+pub use vlsm::{Vlsm, VlsmError, VlsmPlan, VlsmSubnet};
+pub use zone::{ZoneId, ZONE_MAX_LEN};
+
+/// The `alloc` items which are in the `std` prelude, for `no_std` builds.
+#[allow(unused_imports)]
+mod prelude {
+    pub(crate) use alloc::{
+        borrow::ToOwned,
+        boxed::Box,
+        format,
+        string::{String, ToString},
+        vec,
+        vec::Vec,
+    };
+}
 
 pub(crate) const IPV4_BITS: u8 = 32;
 pub(crate) const IPV6_BITS: u8 = 128;
@@ -36,10 +111,20 @@ pub enum AddressError {
     InvalidRangeEndVal { val: String, source: ParseIntError },
     InvalidV4Octet(u32),
     InvalidV6Hextet(u32),
+    /// CIDR with more than one `/`
+    InvalidCidrFmt(String),
+    InvalidCidrAddr    { addr: String, source: AddrParseError },
+    InvalidCidrPrefix  { prefix: String, source: ParseIntError },
+    /// prefix longer than the address family allows
+    PrefixTooLong(IpFam, u8),
     RangeTooLarge(u128),
     RangeOrder(IpAddr, IpAddr),
     /// start and end are not the same IP family (v4 vs v6).
     Mismatch(IpAddr, IpAddr),
+    /// invalid zone ID, or a zone on an IPv4 address
+    InvalidZone(String),
+    /// start and end are in different zones
+    ZoneMismatch(ZoneId, ZoneId),
 }
 
 impl fmt::Display for AddressError {
@@ -57,6 +142,21 @@ impl fmt::Display for AddressError {
             AddressError::InvalidV6Hextet(val) => {
                 write!(f, "{ERR_V6_HEXTET} {val}")
             }
+            AddressError::InvalidCidrFmt(cidr) => {
+                write!(f, "{ERR_CIDR_FMT}: '{cidr}'")
+            }
+            AddressError::InvalidCidrAddr { addr, .. } => {
+                write!(f, "{ERR_CIDR_INV_ADDR}: '{addr}'")
+            }
+            AddressError::InvalidCidrPrefix { prefix, .. } => {
+                write!(f, "{ERR_CIDR_INV_PRE}: '{prefix}'")
+            }
+            AddressError::PrefixTooLong(IpFam::V4, prefix) => {
+                write!(f, "{ERR_CIDR_INV_V4}: '{prefix}'")
+            }
+            AddressError::PrefixTooLong(IpFam::V6, prefix) => {
+                write!(f, "{ERR_CIDR_INV_V6}: '{prefix}'")
+            }
             AddressError::RangeTooLarge(size) => {
                 write!(f, "{ERR_RNG_TOOLARGE}: {size} (max {MAX_RANGE_SIZE})")
             }
@@ -66,17 +166,35 @@ impl fmt::Display for AddressError {
             AddressError::Mismatch(a, b) => {
                 write!(f, "{ERR_MISMATCH}: {a} - {b}")
             }
-            AddressError::InvalidRangeBegIp { beg, source } => {
-                write!(f, "{ERR_START}: '{beg}': {source}")
+            AddressError::InvalidRangeBegIp { beg, .. } => {
+                write!(f, "{ERR_START}: '{beg}'")
+            }
+            AddressError::InvalidRangeEndIp { end, .. } => {
+                write!(f, "{ERR_END}: '{end}'")
             }
-            AddressError::InvalidRangeEndIp { end, source } => {
-                write!(f, "{ERR_END}: '{end}': {source}")
+            AddressError::InvalidRangeEndVal { val, .. } => {
+                write!(f, "{ERR_RNG_END}: '{val}'")
             }
-            AddressError::InvalidRangeEndVal { val, source } => {
-                write!(f, "{ERR_RNG_END}: '{val}': {source}")
+            AddressError::InvalidZone(zone) => {
+                write!(f, "{ERR_ZONE_INVALID}: '{zone}'")
+            }
+            AddressError::ZoneMismatch(a, b) => {
+                write!(f, "{ERR_ZONE_MISMATCH}: {a} - {b}")
             }
         }
     }
 }
 
-impl error::Error for AddressError {}
+impl error::Error for AddressError {
+    /// The parse error behind an invalid part. It is not repeated in the message.
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            AddressError::InvalidRangeBegIp { source, .. }
+            | AddressError::InvalidRangeEndIp { source, .. }
+            | AddressError::InvalidCidrAddr { source, .. } => Some(source),
+            AddressError::InvalidRangeEndVal { source, .. }
+            | AddressError::InvalidCidrPrefix { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,249 @@
+// Copyright (c) 2026 Mikko Tanner. All rights reserved.
+// Licensed under the MIT License or the Apache License, Version 2.0.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Overlap (conflict) detection across named address lists.
+
+#[cfg(feature = "tabulate")]
+use super::structs::RangeFormat;
+use super::{
+    collapsing::{iprange_to_range, range_to_cidrs, range_to_iprange},
+    prelude::*,
+    structs::{Cidr, IpRange, Range},
+    tagged::label_segments,
+    AddressError,
+};
+#[cfg(feature = "tabulate")]
+use crate::simple_tabulate;
+use alloc::collections::BTreeSet;
+use core::fmt;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// An input entry involved in an [Overlap].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OverlapEntry {
+    /// name of the list
+    pub list: String,
+    /// 0-based position of the entry in its list
+    pub index: usize,
+    /// the entry as displayed; [AddressSpec](super::AddressSpec) entries keep
+    /// the notation they were parsed from, [IpRange] entries are full ranges
+    pub entry: String,
+}
+
+impl fmt::Display for OverlapEntry {
+    /// `blocklist[3] 10.0.0.0/24`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}[{}] {}", self.list, self.index, self.entry)
+    }
+}
+
+/// Addresses which are in more than one list, see [find_overlaps].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Overlap {
+    /// the shared addresses
+    pub range: IpRange,
+    /// `range` as minimal CIDRs
+    pub cidrs: Vec<Cidr>,
+    pub addresses: u128,
+    /// names of the lists involved, sorted
+    pub lists: Vec<String>,
+    /// all entries covering `range`, in input order
+    pub entries: Vec<OverlapEntry>,
+}
+
+/// Result of [find_overlaps].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OverlapReport {
+    /// in address order (IPv4 first)
+    pub overlaps: Vec<Overlap>,
+}
+
+impl OverlapReport {
+    pub fn is_empty(&self) -> bool {
+        self.overlaps.is_empty()
+    }
+
+    /// Number of addresses in more than one list. Saturating.
+    pub fn addresses(&self) -> u128 {
+        self.overlaps
+            .iter()
+            .map(|o| o.addresses)
+            .fold(0, u128::saturating_add)
+    }
+
+    /// Render the report as a table for printing, one overlap per row.
+    #[cfg(feature = "tabulate")]
+    pub fn to_table(&self) -> Vec<String> {
+        simple_tabulate(
+            self.overlaps.iter().map(|o| {
+                let entries: Vec<String> = o.entries.iter().map(|e| e.to_string()).collect();
+                [
+                    o.range.format(RangeFormat::Shortest),
+                    o.addresses.to_string(),
+                    o.lists.join(", "),
+                    entries.join(", "),
+                ]
+            }),
+            Some(&["overlap", "addresses", "lists", "entries"]),
+        )
+    }
+}
+
+/**
+Find every address range which belongs to more than one of the named
+`lists`, f.ex. addresses which are both allowed and blocked. Entries can be
+anything convertible to an [IpRange] ([Cidr], [IpRange], IpAddr). Use
+[AddressSpec](super::AddressSpec) lists to report entries in the notation
+they were written in.
+
+Overlaps between entries of the *same* list are not reported. Each
+reported range is maximal for its set of covering entries, so an entry
+partially overlapping two others yields separate rows.
+
+Runs as a single sweep over the sorted entries, in O(n log n) for n
+entries plus the size of the output.
+*/
+pub fn find_overlaps<S, T>(lists: &[(S, Vec<T>)]) -> Result<OverlapReport, AddressError>
+where
+    S: AsRef<str>,
+    T: Copy + Into<IpRange> + fmt::Display,
+{
+    // entries are labelled by their position in the flattened input
+    let mut owners: Vec<(usize, usize)> = Vec::new();
+    let mut ranges: Vec<(Range, usize)> = Vec::new();
+    for (l, (_, entries)) in lists.iter().enumerate() {
+        for (i, entry) in entries.iter().enumerate() {
+            ranges.push((iprange_to_range((*entry).into())?, owners.len()));
+            owners.push((l, i));
+        }
+    }
+
+    let mut overlaps: Vec<Overlap> = Vec::new();
+    for (r, labels) in label_segments(ranges) {
+        let in_lists: BTreeSet<usize> = labels.iter().map(|id| owners[*id].0).collect();
+        if in_lists.len() < 2 {
+            continue;
+        }
+        let mut list_names: Vec<String> = in_lists
+            .iter()
+            .map(|l| lists[*l].0.as_ref().to_string())
+            .collect();
+        list_names.sort();
+        overlaps.push(Overlap {
+            range: range_to_iprange(r),
+            cidrs: range_to_cidrs(r),
+            addresses: r.len(),
+            lists: list_names,
+            entries: labels
+                .iter()
+                .map(|id| {
+                    let (l, i): (usize, usize) = owners[*id];
+                    OverlapEntry {
+                        list: lists[l].0.as_ref().to_string(),
+                        index: i,
+                        entry: lists[l].1[i].to_string(),
+                    }
+                })
+                .collect(),
+        });
+    }
+    Ok(OverlapReport { overlaps })
+}
+
+/* -------------------------------------------------------------------------- */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::iptools::{AddressSpec, RangeFormat};
+
+    fn lists() -> Vec<(&'static str, Vec<AddressSpec>)> {
+        let parse =
+            |v: &[&str]| -> Vec<AddressSpec> { v.iter().map(|s| s.parse().unwrap()).collect() };
+        vec![
+            ("office", parse(&["10.0.0.0/24", "10.0.1.0/24"])),
+            ("vpn", parse(&["10.0.0.128-10.0.1.15", "192.168.0.0/16"])),
+            (
+                "blocklist",
+                parse(&["10.0.1.10", "203.0.113.0/24", "2001:db8::/32"]),
+            ),
+            (
+                "partners",
+                parse(&["192.168.0.0/24", "192.168.0.0/25", "2001:db9::/32"]),
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_find_overlaps() {
+        let report: OverlapReport = find_overlaps(&lists()).unwrap();
+        let rows: Vec<(String, Vec<String>, usize)> = report
+            .overlaps
+            .iter()
+            .map(|o| {
+                (
+                    o.range.format(RangeFormat::Shortest),
+                    o.lists.clone(),
+                    o.entries.len(),
+                )
+            })
+            .collect();
+        let row = |r: &str, lists: &[&str], n: usize| {
+            (
+                r.to_string(),
+                lists.iter().map(|s| s.to_string()).collect(),
+                n,
+            )
+        };
+        assert_eq!(
+            rows,
+            [
+                row("10.0.0.128/25", &["office", "vpn"], 2),
+                row("10.0.1.0-9", &["office", "vpn"], 2),
+                row("10.0.1.10", &["blocklist", "office", "vpn"], 3),
+                row("10.0.1.11-15", &["office", "vpn"], 2),
+                // both partner entries; their overlap with each other alone is not reported
+                row("192.168.0.0/25", &["partners", "vpn"], 3),
+                row("192.168.0.128/25", &["partners", "vpn"], 2),
+            ]
+        );
+        assert_eq!(
+            report.overlaps[0].cidrs,
+            ["10.0.0.128/25".parse::<Cidr>().unwrap()]
+        );
+        assert_eq!(
+            report.overlaps[2].entries[0].to_string(),
+            "office[1] 10.0.1.0/24"
+        );
+        assert_eq!(report.addresses(), 128 + 10 + 1 + 5 + 256);
+
+        #[cfg(feature = "tabulate")]
+        {
+            let table: Vec<String> = report.to_table();
+            assert_eq!(table.len(), 2 + report.overlaps.len());
+            assert!(table[4].contains("vpn[0] 10.0.0.128-10.0.1.15, blocklist[0] 10.0.1.10"));
+        }
+
+        // IpRange entries are shown as full ranges
+        let ranges: Vec<(&str, Vec<IpRange>)> = lists()
+            .into_iter()
+            .map(|(name, specs)| (name, specs.into_iter().map(IpRange::from).collect()))
+            .collect();
+        let report: OverlapReport = find_overlaps(&ranges).unwrap();
+        assert_eq!(
+            report.overlaps[2].entries[2].to_string(),
+            "blocklist[0] 10.0.1.10-10.0.1.10"
+        );
+
+        let none: [(&str, Vec<Cidr>); 2] = [
+            ("a", vec!["10.0.0.0/8".parse().unwrap()]),
+            ("b", vec!["fe80::/10".parse().unwrap()]),
+        ];
+        assert!(find_overlaps(&none).unwrap().is_empty());
+    }
+}
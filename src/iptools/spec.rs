@@ -0,0 +1,240 @@
+// Copyright (c) 2026 Mikko Tanner. All rights reserved.
+// Licensed under the MIT License or the Apache License, Version 2.0.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Typed address input which remembers its notation.
+
+use super::{
+    addresses::parse_ip_range_notation,
+    collapsing::{cidr_to_range, ip_to_host_cidr, iprange_to_range},
+    prelude::*,
+    strings::*,
+    structs::{Cidr, IpRange, IpRangeIterator, Range, RangeFormat},
+    zone::{parse_zoned_ip, ZoneId},
+    AddressError,
+};
+use core::{fmt, net::IpAddr, str::FromStr};
+#[cfg(feature = "serde")]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/**
+A single IP, a CIDR or a range, as written by the user.
+
+Unlike [parse_ip_or_range](super::parse_ip_or_range), parsing does not
+enumerate addresses, and [Display](fmt::Display) writes the spec back in
+the notation it was parsed from: `10.0.0.1-9` stays a short range and
+`10.1.2.3/8` keeps its host bits. Serialized as that same string.
+
+More notations may be added, so matches need a wildcard arm.
+*/
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AddressSpec {
+    /// `10.0.0.1`, `fe80::1%eth0`
+    Ip { addr: IpAddr, zone: Option<ZoneId> },
+    /// `10.0.0.0/24`, `fe80::%eth0/64`
+    Cidr(Cidr),
+    /// `10.0.0.1-10.0.0.9`, or `10.0.0.1-9` if `short`
+    Range { range: IpRange, short: bool },
+}
+
+impl AddressSpec {
+    /// Number of addresses covered, never zero. Saturating.
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> u128 {
+        match self {
+            AddressSpec::Ip { .. } => 1,
+            AddressSpec::Cidr(cidr) => cidr.len(),
+            AddressSpec::Range { range, .. } => range.len(),
+        }
+    }
+
+    /// The IPv6 zone, if any.
+    pub fn zone(&self) -> Option<ZoneId> {
+        match self {
+            AddressSpec::Ip { zone, .. } => *zone,
+            AddressSpec::Cidr(cidr) => cidr.zone,
+            AddressSpec::Range { range, .. } => range.zone,
+        }
+    }
+
+    /// The inclusive range covered, see [IpRange::from] for CIDRs.
+    pub fn to_range(&self) -> IpRange {
+        match self {
+            AddressSpec::Ip { addr, zone } => IpRange::from(*addr).with_zone(*zone),
+            AddressSpec::Cidr(cidr) => cidr.to_range(),
+            AddressSpec::Range { range, .. } => *range,
+        }
+    }
+
+    /**
+    Iterate over all the addresses covered. For a CIDR, that includes the
+    network and broadcast addresses, unlike [Cidr::hosts].
+    */
+    pub fn iter(&self) -> IpRangeIterator {
+        self.to_range().iter()
+    }
+
+    /// The minimal list of CIDRs covering exactly this spec.
+    pub fn to_cidrs(&self) -> Vec<Cidr> {
+        match self {
+            AddressSpec::Ip { addr, zone } => vec![ip_to_host_cidr(*addr).with_zone(*zone)],
+            AddressSpec::Cidr(cidr) => vec![cidr.network()],
+            AddressSpec::Range { range, .. } => range.to_cidrs(),
+        }
+    }
+
+    /// Fails only for a range built from an [IpRange] which mixes families.
+    pub(crate) fn range(&self) -> Result<Range, AddressError> {
+        match self {
+            AddressSpec::Ip { addr, zone } => {
+                Ok(cidr_to_range(ip_to_host_cidr(*addr).with_zone(*zone)))
+            }
+            AddressSpec::Cidr(cidr) => Ok(cidr_to_range(*cidr)),
+            AddressSpec::Range { range, .. } => iprange_to_range(*range),
+        }
+    }
+}
+
+impl fmt::Display for AddressSpec {
+    /// The notation the spec was parsed from, zones written last like [IpRange::format].
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddressSpec::Ip {
+                addr,
+                zone: Some(zone),
+            } => write!(f, "{addr}{PERCENT}{zone}"),
+            AddressSpec::Ip { addr, zone: None } => write!(f, "{addr}"),
+            AddressSpec::Cidr(cidr) => write!(f, "{cidr}"),
+            AddressSpec::Range { range, short } => f.write_str(&range.format(match short {
+                true => RangeFormat::Short,
+                false => RangeFormat::Full,
+            })),
+        }
+    }
+}
+
+impl FromStr for AddressSpec {
+    type Err = AddressError;
+
+    /// Parse a single IP, a CIDR or a range, each optionally with an IPv6 zone.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s: &str = s.trim();
+        if let Ok((addr, zone)) = parse_zoned_ip(s) {
+            return Ok(AddressSpec::Ip { addr, zone });
+        }
+        // zone names may contain dashes, so try CIDRs first
+        match s.parse::<Cidr>() {
+            Ok(cidr) => Ok(AddressSpec::Cidr(cidr)),
+            Err(_) if s.contains(DASH) => {
+                let (range, short) = parse_ip_range_notation(s)?;
+                Ok(AddressSpec::Range { range, short })
+            }
+            Err(_) => Err(AddressError::Invalid(s.to_string())),
+        }
+    }
+}
+
+impl From<IpAddr> for AddressSpec {
+    fn from(addr: IpAddr) -> Self {
+        AddressSpec::Ip { addr, zone: None }
+    }
+}
+
+impl From<Cidr> for AddressSpec {
+    fn from(cidr: Cidr) -> Self {
+        AddressSpec::Cidr(cidr)
+    }
+}
+
+impl From<IpRange> for AddressSpec {
+    fn from(range: IpRange) -> Self {
+        AddressSpec::Range {
+            range,
+            short: false,
+        }
+    }
+}
+
+impl From<AddressSpec> for IpRange {
+    fn from(spec: AddressSpec) -> Self {
+        spec.to_range()
+    }
+}
+
+impl IntoIterator for AddressSpec {
+    type Item = IpAddr;
+    type IntoIter = IpRangeIterator;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for AddressSpec {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for AddressSpec {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s: String = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+/* -------------------------------------------------------------------------- */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rustfmt::skip]
+    const ROUND_TRIP: &[(&str, u128)] = &[
+        ("10.0.0.1",                 1),
+        ("fe80::1%eth0",             1),
+        ("10.1.2.3/8",               1 << 24),
+        ("fe80::%br-lan/64",         1 << 64),
+        ("10.0.0.1-9",               9),
+        ("10.0.0.250-10.0.1.5",      12),
+        ("fe80::1-255%br-lan",       255),
+        ("2001:db8::-2001:db8::1:0", 65537),
+    ];
+
+    #[test]
+    fn test_parse_round_trip() {
+        for (s, len) in ROUND_TRIP {
+            let spec: AddressSpec = s.parse().unwrap();
+            assert_eq!(spec.to_string(), *s);
+            assert_eq!(spec.len(), *len, "{s}");
+            assert_eq!(spec.iter().take(3).count() as u128, (*len).min(3));
+        }
+        assert!(matches!(
+            "10.0.0.1 - 9".parse(),
+            Ok(AddressSpec::Range { short: true, .. })
+        ));
+        // the alternative zone notation is normalized
+        assert_eq!(
+            "fe80::/64%eth0".parse::<AddressSpec>().unwrap().to_string(),
+            "fe80::%eth0/64"
+        );
+        for bad in ["", "10.0.0", "10.0.0.9-1", "10.0.0.1%eth0", "10.0.0.0/33"] {
+            assert!(bad.parse::<AddressSpec>().is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_conversions() {
+        let spec: AddressSpec = "10.1.2.3/8".parse().unwrap();
+        assert_eq!(spec.to_cidrs(), ["10.0.0.0/8".parse::<Cidr>().unwrap()]);
+        let spec: AddressSpec = "10.0.0.1-6".parse().unwrap();
+        assert_eq!(spec.to_cidrs().len(), 4);
+        assert_eq!(IpRange::from(spec), "10.0.0.1-10.0.0.6".parse().unwrap());
+        let spec: AddressSpec = "fe80::1%eth0".parse().unwrap();
+        assert_eq!(spec.zone(), Some("eth0".parse().unwrap()));
+        assert_eq!(spec.to_cidrs()[0].to_string(), "fe80::1%eth0/128");
+    }
+}
@@ -0,0 +1,296 @@
+// Copyright (c) 2026 Mikko Tanner. All rights reserved.
+// Licensed under the MIT License or the Apache License, Version 2.0.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Address-space statistics for CIDR sets.
+
+use super::{
+    classify::{special_blocks, v6_range, V6_GLOBAL_UNICAST},
+    collapsing::{cidr_to_range, intersect_ranges, merge_ranges, range_to_cidrs, subtract_ranges},
+    prelude::*,
+    structs::{Cidr, IpFam, Range},
+};
+#[cfg(feature = "tabulate")]
+use crate::tabulate_with_missing;
+use alloc::collections::BTreeMap;
+use core::cmp::Reverse;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "tabulate")]
+static MISSING: &str = "-";
+
+/// Statistics for the blocks of a single address family.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FamilyStats {
+    /// number of input blocks
+    pub blocks: usize,
+    /// number of blocks in the minimal (collapsed) representation
+    pub minimal_blocks: usize,
+    /// distinct addresses covered (overlaps are counted once). Saturating.
+    pub addresses: u128,
+    /// prefix length -> number of input blocks
+    pub histogram: BTreeMap<u8, usize>,
+    pub largest: Option<Cidr>,
+    pub smallest: Option<Cidr>,
+    /// `blocks / minimal_blocks`; 1.0 means the input is already minimal
+    pub fragmentation: f64,
+    /// percentage of the globally routable address space covered
+    pub public_pct: f64,
+    /// percentage of the reference supernet covered, if it is of this family
+    pub reference_pct: Option<f64>,
+}
+
+/// Summary of a CIDR set, see [cidr_stats].
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CidrStats {
+    pub v4: FamilyStats,
+    pub v6: FamilyStats,
+    pub reference: Option<Cidr>,
+}
+
+/**
+Summarize a CIDR set for capacity and risk reporting.
+
+Coverage is reported both against the globally routable space of each
+family (IPv4 minus the IANA special-purpose blocks, IPv6 `2000::/3` minus
+its documentation/protocol blocks) and, if given, against a `reference`
+supernet. IPv6 zones are ignored, as address space is the same in every zone.
+*/
+pub fn cidr_stats(input: &[Cidr], reference: Option<Cidr>) -> CidrStats {
+    CidrStats {
+        v4: family_stats(input, IpFam::V4, reference),
+        v6: family_stats(input, IpFam::V6, reference),
+        reference,
+    }
+}
+
+impl CidrStats {
+    /**
+    Render the summary and the prefix-length histogram as tables for printing
+    (see [simple_tabulate](crate::simple_tabulate)). The two tables are
+    separated by an empty line.
+    */
+    #[cfg(feature = "tabulate")]
+    pub fn to_table(&self) -> Vec<String> {
+        let fams: [&FamilyStats; 2] = [&self.v4, &self.v6];
+        let row = |name: &str, f: &dyn Fn(&FamilyStats) -> Option<String>| {
+            let mut r: Vec<Option<String>> = vec![Some(name.to_string())];
+            r.extend(fams.iter().map(|s| f(s)));
+            r
+        };
+
+        let summary: Vec<Vec<Option<String>>> = vec![
+            row("blocks", &|s| Some(s.blocks.to_string())),
+            row("minimal blocks", &|s| Some(s.minimal_blocks.to_string())),
+            row("fragmentation", &|s| {
+                Some(format!("{:.2}", s.fragmentation))
+            }),
+            row("addresses", &|s| Some(s.addresses.to_string())),
+            row("largest", &|s| s.largest.map(|c| c.to_string())),
+            row("smallest", &|s| s.smallest.map(|c| c.to_string())),
+            row("public space", &|s| Some(format_pct(s.public_pct))),
+            row("reference", &|s| s.reference_pct.map(format_pct)),
+        ];
+        let mut out: Vec<String> =
+            tabulate_with_missing(summary, Some(&["", "IPv4", "IPv6"]), MISSING);
+
+        let mut prefixes: Vec<u8> = self
+            .v4
+            .histogram
+            .keys()
+            .chain(self.v6.histogram.keys())
+            .copied()
+            .collect();
+        prefixes.sort_unstable();
+        prefixes.dedup();
+
+        let histogram = prefixes.iter().map(|p| {
+            let mut r: Vec<Option<String>> = vec![Some(format!("/{p}"))];
+            r.extend(
+                fams.iter()
+                    .map(|s| s.histogram.get(p).map(|n| n.to_string())),
+            );
+            r
+        });
+        out.push(String::new());
+        out.extend(tabulate_with_missing(
+            histogram,
+            Some(&["prefix", "IPv4", "IPv6"]),
+            MISSING,
+        ));
+        out
+    }
+}
+
+/* ---------------------------------- */
+
+fn family_stats(input: &[Cidr], fam: IpFam, reference: Option<Cidr>) -> FamilyStats {
+    let blocks: Vec<Cidr> = input
+        .iter()
+        .map(|c| c.without_zone())
+        .filter(|c| cidr_to_range(*c).fam == fam)
+        .collect();
+    if blocks.is_empty() {
+        return FamilyStats {
+            reference_pct: reference
+                .filter(|r| cidr_to_range(*r).fam == fam)
+                .map(|_| 0.0),
+            ..Default::default()
+        };
+    }
+
+    let mut ranges: Vec<Range> = blocks.iter().map(|c| cidr_to_range(*c)).collect();
+    ranges.sort_by_key(Range::cmp_key);
+    let merged: Vec<Range> = merge_ranges(&ranges);
+
+    let mut histogram: BTreeMap<u8, usize> = BTreeMap::new();
+    for c in &blocks {
+        *histogram.entry(c.prefix).or_default() += 1;
+    }
+
+    let minimal_blocks: usize = merged.iter().map(|r| range_to_cidrs(*r).len()).sum();
+    let public: Vec<Range> = public_space(fam);
+    let reference_pct: Option<f64> = reference
+        .map(cidr_to_range)
+        .filter(|r| r.fam == fam)
+        .map(|r| pct(total_len(&intersect_ranges(&merged, &[r])), r.len()));
+
+    FamilyStats {
+        blocks: blocks.len(),
+        minimal_blocks,
+        addresses: total_len(&merged),
+        histogram,
+        // first of equals wins in both cases
+        largest: blocks.iter().copied().min_by_key(|c| c.prefix),
+        smallest: blocks.iter().copied().min_by_key(|c| Reverse(c.prefix)),
+        fragmentation: blocks.len() as f64 / minimal_blocks as f64,
+        public_pct: pct(
+            total_len(&intersect_ranges(&merged, &public)),
+            total_len(&public),
+        ),
+        reference_pct,
+    }
+}
+
+/**
+The globally routable address space of a family, as sorted ranges: all of
+IPv4 or the IPv6 global unicast block, minus the non-global special-purpose
+blocks of [classify_ip](super::classify_ip).
+*/
+fn public_space(fam: IpFam) -> Vec<Range> {
+    let universe: Range = match fam {
+        IpFam::V4 => Range {
+            fam,
+            zone: None,
+            beg: 0,
+            end: u32::MAX as u128,
+        },
+        IpFam::V6 => v6_range(V6_GLOBAL_UNICAST.0, V6_GLOBAL_UNICAST.1),
+    };
+    let mut reserved: Vec<Range> = special_blocks(fam)
+        .filter(|(_, _, kind)| !kind.is_global())
+        .map(|(r, _, _)| r)
+        .collect();
+    reserved.sort_by_key(Range::cmp_key);
+    subtract_ranges(&[universe], &merge_ranges(&reserved))
+}
+
+/// Total number of addresses in disjoint ranges. Saturating.
+#[inline]
+fn total_len(ranges: &[Range]) -> u128 {
+    ranges
+        .iter()
+        .fold(0u128, |acc, r| acc.saturating_add(r.len()))
+}
+
+#[inline]
+fn pct(part: u128, whole: u128) -> f64 {
+    if whole == 0 {
+        return 0.0;
+    }
+    part as f64 / whole as f64 * 100.0
+}
+
+#[cfg(feature = "tabulate")]
+#[inline]
+fn format_pct(p: f64) -> String {
+    format!("{p:.6}%")
+}
+
+/* -------------------------------------------------------------------------- */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SET: [&str; 5] = [
+        "8.8.8.0/24",
+        "8.8.9.0/24",
+        "10.0.0.0/8",
+        "10.1.0.0/16", // redundant
+        "2001:db8::/48",
+    ];
+    const REFERENCE: &str = "8.0.0.0/8";
+
+    fn set() -> Vec<Cidr> {
+        SET.iter().map(|s| s.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_public_space_sizes() {
+        // 2^32 minus the special-purpose blocks (overlaps counted once)
+        assert_eq!(total_len(&public_space(IpFam::V4)), 3_702_258_432);
+        let v6: u128 = (1u128 << 125) - (1u128 << 105) - (1u128 << 96) - (1u128 << 108);
+        assert_eq!(total_len(&public_space(IpFam::V6)), v6);
+    }
+
+    #[test]
+    fn test_family_stats() {
+        let stats: CidrStats = cidr_stats(&set(), REFERENCE.parse().ok());
+
+        assert_eq!(stats.v4.blocks, 4);
+        assert_eq!(stats.v4.minimal_blocks, 2);
+        assert_eq!(stats.v4.fragmentation, 2.0);
+        assert_eq!(stats.v4.addresses, (1 << 24) + 512);
+        assert_eq!(
+            stats.v4.histogram,
+            BTreeMap::from([(8, 1), (16, 1), (24, 2)])
+        );
+        assert_eq!(stats.v4.largest, "10.0.0.0/8".parse().ok());
+        assert_eq!(stats.v4.smallest, "8.8.8.0/24".parse().ok());
+        assert_eq!(
+            stats.v4.reference_pct,
+            Some(512.0 / (1 << 24) as f64 * 100.0)
+        );
+        // only the 8.8.8.0/23 part is public
+        assert_eq!(stats.v4.public_pct, 512.0 / 3_702_258_432.0 * 100.0);
+
+        assert_eq!(stats.v6.blocks, 1);
+        assert_eq!(stats.v6.addresses, 1 << 80);
+        assert_eq!(stats.v6.public_pct, 0.0);
+        assert_eq!(stats.v6.reference_pct, None);
+    }
+
+    #[test]
+    fn test_empty() {
+        let stats: CidrStats = cidr_stats(&[], None);
+        assert_eq!(stats.v4, FamilyStats::default());
+        assert_eq!(stats.v6, FamilyStats::default());
+    }
+
+    #[cfg(feature = "tabulate")]
+    #[test]
+    fn test_table() {
+        let table: Vec<String> = cidr_stats(&set(), None).to_table();
+        assert!(table[0].contains("IPv4") && table[0].contains("IPv6"));
+        assert!(table
+            .iter()
+            .any(|l| l.starts_with("largest") && l.contains("10.0.0.0/8")));
+        assert!(table
+            .iter()
+            .any(|l| l.starts_with("/24 ") && l.trim_end().ends_with('-')));
+    }
+}
@@ -2,8 +2,20 @@
 // Licensed under the MIT License or the Apache License, Version 2.0.
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+// some messages belong to modules behind optional features
+#![cfg_attr(
+    not(all(
+        feature = "std",
+        feature = "regex",
+        feature = "tabulate",
+        feature = "cloud"
+    )),
+    allow(dead_code)
+)]
+
 pub(crate) static DASH: &str = "-";
 pub(crate) static SLASH: &str = "/";
+pub(crate) static PERCENT: &str = "%";
 
 // addresses.rs
 pub(crate) static ERR_INVALID_IP: &str = "invalid IP address, CIDR, or range";
@@ -19,9 +31,58 @@ pub(crate) static ERR_MISMATCH: &str = "cannot mix IPv4 and IPv6 in range";
 pub(crate) static PANIC_NAUGHTY: &str = "Naughty programmer! Beginning cannot be larger than end!";
 
 // structs.rs
-pub(crate) static ERR_INV_ADDR: &str = "invalid IP address";
 pub(crate) static ERR_CIDR_FMT: &str = "invalid CIDR format (too many slashes)";
 pub(crate) static ERR_CIDR_INV_ADDR: &str = "invalid IP address in CIDR";
 pub(crate) static ERR_CIDR_INV_PRE: &str = "invalid prefix in CIDR";
 pub(crate) static ERR_CIDR_INV_V4: &str = "invalid IPv4 prefix in CIDR";
 pub(crate) static ERR_CIDR_INV_V6: &str = "invalid IPv6 prefix in CIDR";
+
+// zone.rs
+pub(crate) static ERR_ZONE_INVALID: &str = "invalid zone ID";
+pub(crate) static ERR_ZONE_MISMATCH: &str = "range endpoints are in different zones";
+
+// mac.rs
+pub(crate) static ERR_MAC_INVALID: &str = "invalid MAC address";
+pub(crate) static ERR_MAC_PREFIX: &str = "SLAAC needs an IPv6 /64 network, got";
+
+// external.rs
+pub(crate) static ERR_EXT_IO: &str = "spill file I/O error";
+pub(crate) static ERR_EXT_MEMLIMIT: &str = "invalid memory limit";
+pub(crate) static ERR_EXT_MEMLIMIT_SIZE: &str = "memory limit too large for this platform";
+pub(crate) static ERR_EXT_CORRUPT: &str = "truncated or corrupt spill file";
+
+// binary.rs
+pub(crate) static ERR_BIN_IO: &str = "I/O error while writing IP set";
+pub(crate) static ERR_BIN_LINE: &str = "invalid entry on line";
+pub(crate) static ERR_BIN_MAGIC: &str = "not an IP set (bad magic)";
+pub(crate) static ERR_BIN_VERSION: &str = "unsupported IP set format version";
+pub(crate) static ERR_BIN_LENGTH: &str = "IP set length does not match its header";
+pub(crate) static ERR_BIN_CHECKSUM: &str = "IP set checksum mismatch";
+pub(crate) static ERR_BIN_ORDER: &str = "IP set ranges are not sorted and disjoint";
+
+// expr.rs
+pub(crate) static ERR_EXPR_UNEXPECTED: &str = "unexpected";
+pub(crate) static ERR_EXPR_END: &str = "unexpected end of expression";
+pub(crate) static ERR_EXPR_PAREN: &str = "unclosed parenthesis";
+pub(crate) static ERR_EXPR_LITERAL: &str = "invalid address";
+pub(crate) static ERR_EXPR_NAME: &str = "unknown name";
+
+// ipam.rs
+pub(crate) static ERR_IPAM_OUTSIDE: &str = "block is outside the pool";
+pub(crate) static ERR_IPAM_OVERLAP: &str = "block overlaps an allocation";
+pub(crate) static ERR_IPAM_NOT_ALLOCATED: &str = "block is not allocated";
+pub(crate) static ERR_IPAM_PREFIX: &str = "invalid prefix for the pool";
+pub(crate) static ERR_IPAM_EXHAUSTED: &str = "no free block of size";
+
+// vlsm.rs
+pub(crate) static ERR_VLSM_NO_HOSTS: &str = "subnet needs at least one host";
+pub(crate) static ERR_VLSM_NO_FIT: &str = "plan does not fit in the parent network";
+
+// cloud.rs
+pub(crate) static ERR_CLOUD_IO: &str = "cannot read range document";
+pub(crate) static ERR_CLOUD_JSON: &str = "invalid range document";
+pub(crate) static ERR_CLOUD_PREFIX: &str = "invalid prefix in range document";
+
+// addrmap.rs
+pub(crate) static ERR_MAP_PREFIX: &str = "invalid cell prefix for the map";
+pub(crate) static ERR_MAP_CELLS: &str = "too many cells in the map";
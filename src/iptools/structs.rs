@@ -3,18 +3,25 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use super::{
-    collapsing::{cidr_to_range, int_to_ip},
+    addresses::parse_ip_range,
+    collapsing::{cidr_to_range, int_to_ip, ip_to_host_cidr, iprange_to_range, range_to_cidrs},
+    prelude::*,
     strings::*,
+    zone::{check_zone, ZoneId},
     AddressError, IPV4_BITS, IPV6_BITS,
 };
-use std::{
+use core::{
     fmt,
     net::{IpAddr, Ipv4Addr, Ipv6Addr},
     str::FromStr,
 };
+use ipnet::IpNet;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// IP address family
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum IpFam {
     V4,
     V6,
@@ -24,18 +31,27 @@ pub enum IpFam {
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub(crate) struct Range {
     pub fam: IpFam,
+    /// ranges in different zones never merge, overlap or contain each other
+    pub zone: Option<ZoneId>,
     pub beg: u128,
     /// inclusive
     pub end: u128,
 }
 
 impl Range {
-    pub fn cmp_key(&self) -> (u8, u128, u128) {
+    pub fn cmp_key(&self) -> (u8, Option<ZoneId>, u128, u128) {
+        let (fam_key, zone) = self.space();
+        (fam_key, zone, self.beg, self.end)
+    }
+
+    /// The (family, zone) address space of the range; the leading part of [Range::cmp_key].
+    #[inline]
+    pub fn space(&self) -> (u8, Option<ZoneId>) {
         let fam_key = match self.fam {
             IpFam::V4 => 0u8,
             IpFam::V6 => 1u8,
         };
-        (fam_key, self.beg, self.end)
+        (fam_key, self.zone)
     }
 
     /// The length of the range. Cannot be an [usize] due to IPv6. Saturating.
@@ -46,14 +62,44 @@ impl Range {
         }
         diff.saturating_add(1)
     }
+
+    #[inline]
+    pub fn contains(&self, other: &Range) -> bool {
+        self.space() == other.space() && self.beg <= other.beg && other.end <= self.end
+    }
+
+    #[inline]
+    pub fn overlaps(&self, other: &Range) -> bool {
+        self.space() == other.space() && self.beg <= other.end && other.beg <= self.end
+    }
+
+    #[inline]
+    pub fn intersection(&self, other: &Range) -> Option<Range> {
+        if !self.overlaps(other) {
+            return None;
+        }
+        Some(Range {
+            fam: self.fam,
+            zone: self.zone,
+            beg: self.beg.max(other.beg),
+            end: self.end.min(other.end),
+        })
+    }
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Cidr {
     /// network address
     pub addr: IpAddr,
     /// **v4**: `0..=32`, **v6**: `0..=128`
     pub prefix: u8,
+    /// IPv6 zone, f.ex. `eth0` in `fe80::%eth0/64`
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub zone: Option<ZoneId>,
 }
 
 impl Cidr {
@@ -112,6 +158,76 @@ impl Cidr {
     pub fn iter(&self) -> CidrIterator {
         CidrIterator::new(*self)
     }
+
+    /// The inclusive [IpRange] spanned by this [Cidr] (host bits are ignored).
+    pub fn to_range(&self) -> IpRange {
+        IpRange::from(*self)
+    }
+
+    /// Whether `ip` belongs to this network. A bare [IpAddr] has no zone, so zones are not compared.
+    pub fn contains_ip(&self, ip: IpAddr) -> bool {
+        let host: Cidr = ip_to_host_cidr(ip).with_zone(self.zone);
+        cidr_to_range(*self).contains(&cidr_to_range(host))
+    }
+
+    /**
+    Whether `other` is fully contained in this network (same as [Cidr::is_supernet_of]).
+
+    Like all the set predicates, this treats networks in different zones
+    (including zoned vs. unzoned) as disjoint.
+    */
+    pub fn contains(&self, other: &Cidr) -> bool {
+        cidr_to_range(*self).contains(&cidr_to_range(*other))
+    }
+
+    /// Whether this and `other` share at least one address.
+    pub fn overlaps(&self, other: &Cidr) -> bool {
+        cidr_to_range(*self).overlaps(&cidr_to_range(*other))
+    }
+
+    /**
+    The addresses common to this and `other`, if any.
+
+    Two CIDRs either nest or are disjoint, so the intersection is always the
+    smaller (normalized) one of the two, or None.
+    */
+    pub fn intersection(&self, other: &Cidr) -> Option<Cidr> {
+        let (a, b) = (cidr_to_range(*self), cidr_to_range(*other));
+        let smaller: Cidr = if a.len() <= b.len() { *self } else { *other };
+        a.intersection(&b).map(|_| smaller.network())
+    }
+
+    /// Whether this network is fully contained in `other`.
+    pub fn is_subnet_of(&self, other: &Cidr) -> bool {
+        other.contains(self)
+    }
+
+    /// Whether this network fully contains `other`.
+    pub fn is_supernet_of(&self, other: &Cidr) -> bool {
+        self.contains(other)
+    }
+
+    /// This [Cidr] with the host bits cleared, f.ex. `10.1.2.3/8` -> `10.0.0.0/8`.
+    pub fn network(&self) -> Cidr {
+        let range: Range = cidr_to_range(*self);
+        Cidr {
+            addr: int_to_ip(range.fam, range.beg),
+            prefix: self.prefix,
+            zone: self.zone,
+        }
+    }
+
+    /// This [Cidr] in the given zone. Zones only apply to IPv6 and are ignored for IPv4.
+    pub fn with_zone(mut self, zone: Option<ZoneId>) -> Self {
+        self.zone = zone.filter(|_| self.is_ipv6());
+        self
+    }
+
+    /// This [Cidr] without its zone, see [strip_zones](super::strip_zones).
+    pub fn without_zone(mut self) -> Self {
+        self.zone = None;
+        self
+    }
 }
 
 impl IntoIterator for Cidr {
@@ -124,59 +240,95 @@ impl IntoIterator for Cidr {
 }
 
 impl fmt::Display for Cidr {
+    /// Zoned networks are shown as `fe80::%eth0/64` (RFC 4007, section 11.7).
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}{SLASH}{}", self.addr, self.prefix)
+        match self.zone {
+            Some(zone) => write!(f, "{}{PERCENT}{zone}{SLASH}{}", self.addr, self.prefix),
+            None => write!(f, "{}{SLASH}{}", self.addr, self.prefix),
+        }
     }
 }
 
-impl FromStr for Cidr {
-    type Err = String;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if !s.contains(SLASH) {
-            let addr: IpAddr = s
-                .trim()
-                .parse::<IpAddr>()
-                .map_err(|_| format!("{ERR_INV_ADDR}: '{s}'"))?;
-            return Ok(Cidr {
-                addr,
-                prefix: match addr {
-                    IpAddr::V4(_) => IPV4_BITS,
-                    IpAddr::V6(_) => IPV6_BITS,
-                },
-            });
+impl From<IpNet> for Cidr {
+    fn from(net: IpNet) -> Self {
+        Cidr {
+            addr: net.addr(),
+            prefix: net.prefix_len(),
+            zone: None,
         }
+    }
+}
 
-        let parts: Vec<&str> = s.split(SLASH).collect();
-        if parts.len() != 2 {
-            return Err(format!("{ERR_CIDR_FMT}: '{s}'"));
-        }
+impl From<Cidr> for IpNet {
+    /// Out-of-range prefixes are clamped to the address width. The zone is dropped.
+    fn from(cidr: Cidr) -> Self {
+        let bits: u8 = match cidr.addr {
+            IpAddr::V4(_) => IPV4_BITS,
+            IpAddr::V6(_) => IPV6_BITS,
+        };
+        IpNet::new(cidr.addr, cidr.prefix.min(bits)).expect("prefix is within bounds")
+    }
+}
 
-        let addr: &str = parts[0].trim();
-        let prefix: &str = parts[1].trim();
+impl FromStr for Cidr {
+    type Err = AddressError;
 
-        let addr: IpAddr = addr
-            .parse::<IpAddr>()
-            .map_err(|_| format!("{ERR_CIDR_INV_ADDR}: '{addr}'"))?;
+    /**
+    Parse `addr/prefix` or a bare address (host network). An IPv6 zone may
+    be given either as `fe80::%eth0/64` or as `fe80::/64%eth0`.
+    */
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (s, zone): (String, Option<ZoneId>) = split_cidr_zone(s)?;
+        let s: &str = s.as_str();
+        let (addr, prefix): (&str, Option<&str>) = match s.split_once(SLASH) {
+            Some((_, prefix)) if prefix.contains(SLASH) => {
+                return Err(AddressError::InvalidCidrFmt(s.to_string()))
+            }
+            Some((addr, prefix)) => (addr.trim(), Some(prefix.trim())),
+            None => (s.trim(), None),
+        };
 
-        let prefix: u8 = prefix
-            .parse::<u8>()
-            .map_err(|_| format!("{ERR_CIDR_INV_PRE}: '{prefix}'"))?;
+        let addr: IpAddr =
+            addr.parse::<IpAddr>()
+                .map_err(|source| AddressError::InvalidCidrAddr {
+                    addr: addr.to_string(),
+                    source,
+                })?;
+        let (fam, bits): (IpFam, u8) = match addr {
+            IpAddr::V4(_) => (IpFam::V4, IPV4_BITS),
+            IpAddr::V6(_) => (IpFam::V6, IPV6_BITS),
+        };
 
-        match addr {
-            IpAddr::V4(_) => {
-                if prefix > IPV4_BITS {
-                    return Err(format!("{ERR_CIDR_INV_V4}: '{prefix}'"));
-                }
-            }
-            IpAddr::V6(_) => {
-                if prefix > IPV6_BITS {
-                    return Err(format!("{ERR_CIDR_INV_V6}: '{prefix}'"));
-                }
+        let prefix: u8 = match prefix {
+            Some(prefix) => {
+                prefix
+                    .parse::<u8>()
+                    .map_err(|source| AddressError::InvalidCidrPrefix {
+                        prefix: prefix.to_string(),
+                        source,
+                    })?
             }
+            None => bits,
+        };
+        if prefix > bits {
+            return Err(AddressError::PrefixTooLong(fam, prefix));
         }
 
-        Ok(Cidr { addr, prefix })
+        check_zone(addr, zone, s)?;
+        Ok(Cidr { addr, prefix, zone })
+    }
+}
+
+/// Take the zone out of either CIDR zone notation, returning the rest as `addr/prefix`.
+fn split_cidr_zone(s: &str) -> Result<(String, Option<ZoneId>), AddressError> {
+    let Some((head, tail)) = s.split_once(PERCENT) else {
+        return Ok((s.to_string(), None));
+    };
+    match tail.split_once(SLASH) {
+        // fe80::%eth0/64
+        Some((zone, prefix)) => Ok((format!("{head}{SLASH}{prefix}"), Some(zone.trim().parse()?))),
+        // fe80::/64%eth0 or fe80::1%eth0
+        None => Ok((head.to_string(), Some(tail.trim().parse()?))),
     }
 }
 
@@ -224,11 +376,30 @@ impl Iterator for CidrIterator {
 
 /* -------------------------------------------------------------------------- */
 
+/// String notation of an [IpRange], see [IpRange::format].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum RangeFormat {
+    /// `10.0.0.1-10.0.0.9`
+    #[default]
+    Full,
+    /// `10.0.0.1-9` if only the last octet (IPv4) or hextet (IPv6) differs, else [RangeFormat::Full]
+    Short,
+    /// whichever of a bare address, a CIDR or [RangeFormat::Short] is shortest
+    Shortest,
+}
+
 /// Inclusive range of IP addresses (endpoints are included).
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct IpRange {
     pub beg: IpAddr,
     pub end: IpAddr,
+    /// IPv6 zone shared by both endpoints
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub zone: Option<ZoneId>,
 }
 
 impl IpRange {
@@ -247,7 +418,23 @@ impl IpRange {
             return Err(AddressError::RangeOrder(beg, end));
         }
 
-        Ok(Self { beg, end })
+        Ok(Self {
+            beg,
+            end,
+            zone: None,
+        })
+    }
+
+    /// This [IpRange] in the given zone. Zones only apply to IPv6 and are ignored for IPv4.
+    pub fn with_zone(mut self, zone: Option<ZoneId>) -> Self {
+        self.zone = zone.filter(|_| self.beg.is_ipv6());
+        self
+    }
+
+    /// This [IpRange] without its zone, see [strip_zones](super::strip_zones).
+    pub fn without_zone(mut self) -> Self {
+        self.zone = None;
+        self
     }
 
     pub fn len(&self) -> u128 {
@@ -273,6 +460,98 @@ impl IpRange {
             done: false,
         }
     }
+
+    /// Decompose the range into the minimal list of CIDRs covering it exactly.
+    pub fn to_cidrs(&self) -> Vec<Cidr> {
+        range_to_cidrs(self.range())
+    }
+
+    /// Whether `ip` is within the range. A bare [IpAddr] has no zone, so zones are not compared.
+    pub fn contains_ip(&self, ip: IpAddr) -> bool {
+        let host: Cidr = ip_to_host_cidr(ip).with_zone(self.zone);
+        self.range().contains(&cidr_to_range(host))
+    }
+
+    /// Whether `other` is fully within this range (same as [IpRange::is_supernet_of]).
+    pub fn contains(&self, other: &IpRange) -> bool {
+        self.range().contains(&other.range())
+    }
+
+    /// Whether this and `other` share at least one address.
+    pub fn overlaps(&self, other: &IpRange) -> bool {
+        self.range().overlaps(&other.range())
+    }
+
+    /// The addresses common to this and `other`, if any.
+    pub fn intersection(&self, other: &IpRange) -> Option<IpRange> {
+        let r: Range = self.range().intersection(&other.range())?;
+        Some(IpRange {
+            beg: int_to_ip(r.fam, r.beg),
+            end: int_to_ip(r.fam, r.end),
+            zone: r.zone,
+        })
+    }
+
+    /// Whether this range is fully within `other`.
+    pub fn is_subnet_of(&self, other: &IpRange) -> bool {
+        other.contains(self)
+    }
+
+    /// Whether this range fully contains `other`.
+    pub fn is_supernet_of(&self, other: &IpRange) -> bool {
+        self.contains(other)
+    }
+
+    /**
+    Format the range in the given notation. All of them are accepted by
+    [IpRange::from_str], and a zone is always written last, f.ex.
+    `fe80::1-fe80::9%eth0`.
+    */
+    pub fn format(&self, fmt: RangeFormat) -> String {
+        let zone: String = self
+            .zone
+            .map_or_else(String::new, |z| format!("{PERCENT}{z}"));
+        // the parser reads a short end as the decimal value of the last octet/hextet
+        let short_end: Option<u16> = match (self.beg, self.end) {
+            (IpAddr::V4(a), IpAddr::V4(b)) if a.octets()[..3] == b.octets()[..3] => {
+                Some(b.octets()[3] as u16)
+            }
+            (IpAddr::V6(a), IpAddr::V6(b)) if a.segments()[..7] == b.segments()[..7] => {
+                Some(b.segments()[7])
+            }
+            _ => None,
+        };
+        let full = || format!("{}{DASH}{}{zone}", self.beg, self.end);
+        let short = || match short_end {
+            Some(end) => format!("{}{DASH}{end}{zone}", self.beg),
+            None => full(),
+        };
+
+        match fmt {
+            RangeFormat::Full => full(),
+            RangeFormat::Short => short(),
+            RangeFormat::Shortest if self.beg == self.end => format!("{}{zone}", self.beg),
+            RangeFormat::Shortest => {
+                let range: String = short();
+                match self.to_cidrs().as_slice() {
+                    [cidr] if cidr.to_string().len() <= range.len() => cidr.to_string(),
+                    _ => range,
+                }
+            }
+        }
+    }
+
+    /// Panics on mixed IP families, like [IpRange::len].
+    fn range(&self) -> Range {
+        iprange_to_range(*self).expect(ERR_MISMATCH)
+    }
+}
+
+impl fmt::Display for IpRange {
+    /// [RangeFormat::Full], f.ex. `10.0.0.1-10.0.0.9`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.format(RangeFormat::Full))
+    }
 }
 
 impl IntoIterator for IpRange {
@@ -284,6 +563,48 @@ impl IntoIterator for IpRange {
     }
 }
 
+impl From<IpAddr> for IpRange {
+    /// A single IP is a range of one address.
+    fn from(ip: IpAddr) -> Self {
+        Self {
+            beg: ip,
+            end: ip,
+            zone: None,
+        }
+    }
+}
+
+impl From<Cidr> for IpRange {
+    /// The inclusive range spanned by a [Cidr] (host bits are ignored).
+    fn from(cidr: Cidr) -> Self {
+        let range: Range = cidr_to_range(cidr);
+        Self {
+            beg: int_to_ip(range.fam, range.beg),
+            end: int_to_ip(range.fam, range.end),
+            zone: cidr.zone,
+        }
+    }
+}
+
+impl FromStr for IpRange {
+    type Err = AddressError;
+
+    /**
+    Parse any of the supported notations without enumerating addresses:
+    a range (`10.0.0.1-10.0.0.9`, `10.0.0.1-9`), a CIDR or a single IP,
+    each optionally with an IPv6 zone.
+    */
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s: &str = s.trim();
+        // zone names may contain dashes, so a dash alone does not make a range
+        match s.parse::<Cidr>() {
+            Ok(cidr) => Ok(IpRange::from(cidr)),
+            Err(_) if s.contains(DASH) => parse_ip_range(s),
+            Err(_) => Err(AddressError::Invalid(s.to_string())),
+        }
+    }
+}
+
 /* ---------------------------------- */
 
 /// Iterator over an IP range.
@@ -325,6 +646,7 @@ mod tests {
     const TEST_V4: &str = "192.168.1.0/30";
     const TEST_V6: &str = "::/126";
     const TEST_LEN: &str = "10.0.0.0/8";
+    const TEST_ZONED: &str = "fe80::%eth0/64";
 
     #[test]
     fn test_cidr_parse_v4() {
@@ -377,7 +699,7 @@ mod tests {
             IpAddr::V6(Ipv6Addr::from(2u128)),
             IpAddr::V6(Ipv6Addr::from(3u128)),
         ];
-        assert_eq!(ips, expected); 
+        assert_eq!(ips, expected);
     }
 
     #[test]
@@ -415,4 +737,120 @@ mod tests {
         ];
         assert_eq!(ips, expected);
     }
+
+    #[test]
+    fn test_cidr_predicates() {
+        let net: Cidr = "10.1.2.3/16".parse().unwrap();
+        let sub: Cidr = "10.1.128.0/17".parse().unwrap();
+        let other: Cidr = "10.2.0.0/16".parse().unwrap();
+        let v6: Cidr = "::/0".parse().unwrap();
+
+        assert!(net.contains_ip("10.1.255.255".parse().unwrap()));
+        assert!(!net.contains_ip("10.2.0.0".parse().unwrap()));
+        assert!(!v6.contains_ip("10.1.0.0".parse().unwrap()));
+
+        assert!(net.contains(&sub) && sub.is_subnet_of(&net) && net.is_supernet_of(&sub));
+        assert!(!sub.contains(&net));
+        assert!(net.overlaps(&sub) && !net.overlaps(&other) && !net.overlaps(&v6));
+
+        assert_eq!(net.intersection(&sub), Some(sub));
+        assert_eq!(sub.intersection(&net), Some(sub));
+        assert_eq!(net.intersection(&other), None);
+        assert_eq!(net.network().to_string(), "10.1.0.0/16");
+    }
+
+    #[test]
+    fn test_iprange_predicates() {
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+        let a: IpRange = IpRange::new(ip("10.0.0.10"), ip("10.0.0.20")).unwrap();
+        let b: IpRange = IpRange::new(ip("10.0.0.15"), ip("10.0.0.30")).unwrap();
+        let c: IpRange = IpRange::new(ip("10.0.0.12"), ip("10.0.0.13")).unwrap();
+
+        assert!(a.contains_ip(ip("10.0.0.20")) && !a.contains_ip(ip("10.0.0.21")));
+        assert!(a.overlaps(&b) && !b.overlaps(&c));
+        assert!(a.contains(&c) && c.is_subnet_of(&a) && !a.is_supernet_of(&b));
+        assert_eq!(
+            a.intersection(&b),
+            Some(IpRange::new(ip("10.0.0.15"), ip("10.0.0.20")).unwrap())
+        );
+        assert_eq!(b.intersection(&c), None);
+
+        let cidrs: Vec<String> = a.to_cidrs().iter().map(|c| c.to_string()).collect();
+        assert_eq!(
+            cidrs,
+            [
+                "10.0.0.10/31",
+                "10.0.0.12/30",
+                "10.0.0.16/30",
+                "10.0.0.20/32"
+            ]
+        );
+        assert_eq!(c.to_cidrs()[0].to_range(), c);
+    }
+
+    #[test]
+    fn test_iprange_format() {
+        #[rustfmt::skip]
+        let cases: [(&str, [&str; 3]); 5] = [
+            ("10.0.0.1-10.0.0.9",       ["10.0.0.1-10.0.0.9", "10.0.0.1-9", "10.0.0.1-9"]),
+            ("10.0.0.0-10.0.0.255",     ["10.0.0.0-10.0.0.255", "10.0.0.0-255", "10.0.0.0/24"]),
+            ("10.0.0.5-10.0.1.5",       ["10.0.0.5-10.0.1.5", "10.0.0.5-10.0.1.5", "10.0.0.5-10.0.1.5"]),
+            ("2001:db8::",              ["2001:db8::-2001:db8::", "2001:db8::-0", "2001:db8::"]),
+            ("fe80::1-fe80::ff%br-lan", ["fe80::1-fe80::ff%br-lan", "fe80::1-255%br-lan", "fe80::1-255%br-lan"]),
+        ];
+        let formats: [RangeFormat; 3] =
+            [RangeFormat::Full, RangeFormat::Short, RangeFormat::Shortest];
+        for (input, expected) in cases {
+            let range: IpRange = input.parse().unwrap();
+            for (fmt, exp) in formats.iter().zip(expected) {
+                let s: String = range.format(*fmt);
+                assert_eq!(s, exp, "{input} {fmt:?}");
+                assert_eq!(s.parse::<IpRange>().unwrap(), range, "{s}");
+            }
+        }
+        assert_eq!(
+            IpRange::from(TEST_ZONED.parse::<Cidr>().unwrap()).to_string(),
+            "fe80::-fe80::ffff:ffff:ffff:ffff%eth0"
+        );
+    }
+
+    #[test]
+    fn test_ipnet_conversions() {
+        let net: IpNet = "2001:db8::1/64".parse().unwrap();
+        let cidr: Cidr = net.into();
+        assert_eq!(cidr.to_string(), "2001:db8::1/64");
+        assert_eq!(IpNet::from(cidr), net);
+        assert_eq!(
+            IpNet::from(Cidr {
+                addr: IpAddr::V4(Ipv4Addr::LOCALHOST),
+                prefix: 99,
+                zone: None,
+            })
+            .prefix_len(),
+            32
+        );
+    }
+
+    #[test]
+    fn test_zoned_cidr() {
+        let cidr: Cidr = TEST_ZONED.parse().unwrap();
+        assert_eq!(cidr.zone.unwrap().as_str(), "eth0");
+        assert_eq!(cidr.to_string(), TEST_ZONED);
+        // zone after the prefix is accepted too
+        assert_eq!("fe80::/64%eth0".parse::<Cidr>().unwrap(), cidr);
+        assert_eq!("fe80::1%eth0".parse::<Cidr>().unwrap().prefix, 128);
+        assert!("10.0.0.0/8%eth0".parse::<Cidr>().is_err());
+        assert!("fe80::/64%".parse::<Cidr>().is_err());
+
+        // different zones are different networks
+        let plain: Cidr = cidr.without_zone();
+        assert!(!plain.contains(&cidr) && !cidr.overlaps(&plain));
+        assert!(cidr.contains(&"fe80::1%eth0".parse().unwrap()));
+        assert!(cidr.contains_ip("fe80::1".parse().unwrap()));
+
+        let range: IpRange = "fe80::1-fe80::ff%br-lan".parse().unwrap();
+        assert_eq!(range.zone.unwrap().as_str(), "br-lan");
+        assert_eq!(range.to_cidrs()[0].to_string(), "fe80::1%br-lan/128");
+        assert_eq!(IpRange::from(cidr).zone, cidr.zone);
+    }
 }
@@ -0,0 +1,280 @@
+// Copyright (c) 2026 Mikko Tanner. All rights reserved.
+// Licensed under the MIT License or the Apache License, Version 2.0.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Collapsing which keeps track of where each block came from.
+
+use super::{
+    collapsing::{cidr_to_range, range_to_cidrs},
+    prelude::*,
+    structs::{Cidr, Range},
+    zone::ZoneId,
+};
+use alloc::collections::{BTreeMap, BTreeSet};
+use core::fmt;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Ranges of one (family, zone) address space with their interned labels.
+type SpaceRanges = BTreeMap<(u8, Option<ZoneId>), Vec<(Range, usize)>>;
+
+/// A [Cidr] with the labels of all the input blocks which cover it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TaggedCidr<L: Ord> {
+    pub cidr: Cidr,
+    pub labels: BTreeSet<L>,
+}
+
+impl<L: Ord + fmt::Display> fmt::Display for TaggedCidr<L> {
+    /// `10.0.0.0/24 [ops, web]`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let labels: Vec<String> = self.labels.iter().map(|l| l.to_string()).collect();
+        write!(f, "{} [{}]", self.cidr, labels.join(", "))
+    }
+}
+
+/**
+Collapse labelled CIDRs into minimal CIDRs, each carrying the set of labels
+whose input blocks cover it.
+
+With `merge_different == false` only addresses with the *same* label set
+are merged, so every address of an output block is covered by exactly the
+blocks its labels name. With `merge_different == true` all overlapping or
+adjacent blocks are merged as in [collapse_cidrs](super::collapse_cidrs),
+and each output block gets the labels of the input blocks which cover any
+part of it.
+
+Output is sorted by address (IPv4 first), like the untagged variants.
+*/
+pub fn collapse_tagged<L: Ord + Clone>(
+    input: &[(Cidr, L)],
+    merge_different: bool,
+) -> Vec<TaggedCidr<L>> {
+    // intern the labels so that the sweep works on small integer sets
+    let mut labels: Vec<&L> = input.iter().map(|(_, l)| l).collect();
+    labels.sort();
+    labels.dedup();
+    let label_idx = |l: &L| labels.binary_search(&l).expect("label was interned");
+
+    let ranges: Vec<(Range, usize)> = input
+        .iter()
+        .map(|(cidr, label)| (cidr_to_range(*cidr), label_idx(label)))
+        .collect();
+    let segments: Vec<(Range, BTreeSet<usize>)> = match merge_different {
+        true => by_space(ranges)
+            .into_values()
+            .flat_map(merge_labelled)
+            .collect(),
+        false => label_segments(ranges),
+    };
+
+    let mut out: Vec<TaggedCidr<L>> = Vec::new();
+    for (r, set) in segments {
+        let set: BTreeSet<L> = set.iter().map(|i| labels[*i].clone()).collect();
+        out.extend(range_to_cidrs(r).into_iter().map(|cidr| TaggedCidr {
+            cidr,
+            labels: set.clone(),
+        }));
+    }
+    out
+}
+
+/* ---------------------------------- */
+
+/**
+Split labelled ranges into maximal segments with a constant, non-empty set
+of labels covering them. Segments are sorted like [Range::cmp_key] and
+disjoint; adjacent ones in the same address space differ in their labels.
+*/
+pub(crate) fn label_segments(ranges: Vec<(Range, usize)>) -> Vec<(Range, BTreeSet<usize>)> {
+    by_space(ranges).into_values().flat_map(sweep).collect()
+}
+
+/// Group labelled ranges by (family, zone) address space, in address order.
+fn by_space(ranges: Vec<(Range, usize)>) -> SpaceRanges {
+    let mut spaces: SpaceRanges = BTreeMap::new();
+    for (r, label) in ranges {
+        spaces.entry(r.space()).or_default().push((r, label));
+    }
+    spaces
+}
+
+/**
+Split the ranges of one address space into maximal segments with a constant
+label set. Segments are sorted and disjoint; adjacent ones always differ in
+their labels.
+*/
+fn sweep(ranges: Vec<(Range, usize)>) -> Vec<(Range, BTreeSet<usize>)> {
+    let Some(&(template, _)) = ranges.first() else {
+        return Vec::new();
+    };
+
+    // (position, label, +1 when the label starts / -1 when it ends before position)
+    let mut events: Vec<(u128, usize, i8)> = Vec::with_capacity(ranges.len() * 2);
+    for (r, label) in &ranges {
+        events.push((r.beg, *label, 1));
+        // a range ending at the top of the address space never ends
+        if let Some(after) = r.end.checked_add(1) {
+            events.push((after, *label, -1));
+        }
+    }
+    events.sort_unstable();
+
+    let mut out: Vec<(Range, BTreeSet<usize>)> = Vec::new();
+    let mut active: BTreeMap<usize, usize> = BTreeMap::new();
+    let mut seg_beg: u128 = 0;
+    let mut i: usize = 0;
+    while i < events.len() {
+        let pos: u128 = events[i].0;
+        if !active.is_empty() && pos > seg_beg {
+            push_segment(&mut out, template, seg_beg, pos - 1, &active);
+        }
+        while i < events.len() && events[i].0 == pos {
+            let (_, label, delta) = events[i];
+            match delta {
+                1 => *active.entry(label).or_default() += 1,
+                _ => {
+                    let n: &mut usize = active.get_mut(&label).expect("ended label is active");
+                    *n -= 1;
+                    if *n == 0 {
+                        active.remove(&label);
+                    }
+                }
+            }
+            i += 1;
+        }
+        seg_beg = pos;
+    }
+    if !active.is_empty() {
+        push_segment(&mut out, template, seg_beg, u128::MAX, &active);
+    }
+    out
+}
+
+/// Append a segment, merging it into the previous one if adjacent with the same labels.
+#[inline]
+fn push_segment(
+    out: &mut Vec<(Range, BTreeSet<usize>)>,
+    template: Range,
+    beg: u128,
+    end: u128,
+    active: &BTreeMap<usize, usize>,
+) {
+    let set: BTreeSet<usize> = active.keys().copied().collect();
+    if let Some((last, last_set)) = out.last_mut() {
+        if last.end.checked_add(1) == Some(beg) && *last_set == set {
+            last.end = end;
+            return;
+        }
+    }
+    out.push((
+        Range {
+            beg,
+            end,
+            ..template
+        },
+        set,
+    ));
+}
+
+/**
+Merge all overlapping/adjacent ranges of one address space and cut them into
+CIDR-sized ranges, each with the labels of the input ranges intersecting it.
+*/
+fn merge_labelled(ranges: Vec<(Range, usize)>) -> Vec<(Range, BTreeSet<usize>)> {
+    let segments: Vec<(Range, BTreeSet<usize>)> = sweep(ranges);
+    let mut out: Vec<(Range, BTreeSet<usize>)> = Vec::new();
+    let mut first: usize = 0; // first segment which can intersect the next CIDR
+    let mut i: usize = 0;
+    while i < segments.len() {
+        // a merged range is a maximal chain of adjacent segments
+        let mut run: Range = segments[i].0;
+        i += 1;
+        while i < segments.len() && run.end.checked_add(1) == Some(segments[i].0.beg) {
+            run.end = segments[i].0.end;
+            i += 1;
+        }
+        for cidr in range_to_cidrs(run) {
+            let r: Range = cidr_to_range(cidr);
+            while segments[first].0.end < r.beg {
+                first += 1;
+            }
+            let labels: BTreeSet<usize> = segments[first..i]
+                .iter()
+                .take_while(|(seg, _)| seg.beg <= r.end)
+                .flat_map(|(_, set)| set.iter().copied())
+                .collect();
+            out.push((r, labels));
+        }
+    }
+    out
+}
+
+/* -------------------------------------------------------------------------- */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rustfmt::skip]
+    const INPUT: [(&str, &str); 5] = [
+        ("10.0.0.0/24",     "ops"),
+        ("10.0.0.0/25",     "web"),
+        ("10.0.1.0/24",     "ops"),
+        ("10.0.2.0/24",     "web"),
+        ("2001:db8::/64",   "ops"),
+    ];
+
+    fn input() -> Vec<(Cidr, String)> {
+        INPUT
+            .iter()
+            .map(|(c, l)| (c.parse().unwrap(), l.to_string()))
+            .collect()
+    }
+
+    fn render(out: &[TaggedCidr<String>]) -> Vec<String> {
+        out.iter().map(|t| t.to_string()).collect()
+    }
+
+    #[test]
+    fn test_keeps_label_sets_apart() {
+        assert_eq!(
+            render(&collapse_tagged(&input(), false)),
+            [
+                "10.0.0.0/25 [ops, web]",
+                "10.0.0.128/25 [ops]",
+                "10.0.1.0/24 [ops]",
+                "10.0.2.0/24 [web]",
+                "2001:db8::/64 [ops]",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_different() {
+        assert_eq!(
+            render(&collapse_tagged(&input(), true)),
+            [
+                "10.0.0.0/23 [ops, web]",
+                // only the blocks covering each output block are named
+                "10.0.2.0/24 [web]",
+                "2001:db8::/64 [ops]",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_edges() {
+        let all: Vec<(Cidr, u8)> = vec![("::/0".parse().unwrap(), 1), ("::/1".parse().unwrap(), 2)];
+        let out: Vec<TaggedCidr<u8>> = collapse_tagged(&all, false);
+        assert_eq!(out.len(), 2);
+        assert_eq!(out[1].cidr.to_string(), "8000::/1");
+        assert_eq!(out[1].labels, BTreeSet::from([1]));
+        let merged: Vec<TaggedCidr<u8>> = collapse_tagged(&all, true);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].labels, BTreeSet::from([1, 2]));
+        assert!(collapse_tagged::<u8>(&[], false).is_empty());
+        assert!(collapse_tagged::<u8>(&[], true).is_empty());
+    }
+}
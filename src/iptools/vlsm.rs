@@ -0,0 +1,347 @@
+// Copyright (c) 2026 Mikko Tanner. All rights reserved.
+// Licensed under the MIT License or the Apache License, Version 2.0.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! VLSM subnet planning from host-count requirements.
+
+#[cfg(feature = "tabulate")]
+use super::info::CidrInfo;
+use super::{
+    ipam::{FitStrategy, Ipam},
+    prelude::*,
+    strings::*,
+    structs::Cidr,
+    IPV4_BITS, IPV6_BITS,
+};
+#[cfg(feature = "tabulate")]
+use crate::simple_tabulate;
+use core::{error, fmt, net::IpAddr};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "tabulate")]
+static UNUSED: &str = "(unused)";
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum VlsmError {
+    /// a requirement of zero hosts
+    NoHosts(String),
+    /// the subnets need more addresses than the parent has (saturating)
+    DoesNotFit { needed: u128, available: u128 },
+}
+
+impl fmt::Display for VlsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VlsmError::NoHosts(name) => write!(f, "{ERR_VLSM_NO_HOSTS}: '{name}'"),
+            VlsmError::DoesNotFit { needed, available } => write!(
+                f,
+                "{ERR_VLSM_NO_FIT}: {needed} addresses needed, {available} available"
+            ),
+        }
+    }
+}
+
+impl error::Error for VlsmError {}
+
+/// One planned subnet, see [VlsmPlan].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct VlsmSubnet {
+    pub name: String,
+    pub hosts_required: u128,
+    pub cidr: Cidr,
+    /// usable hosts of `cidr`, as in [Cidr::hosts]
+    pub hosts: u128,
+}
+
+/// Result of [Vlsm::plan].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct VlsmPlan {
+    pub parent: Cidr,
+    /// in address order, which is also largest first
+    pub subnets: Vec<VlsmSubnet>,
+    /// space of the parent left over, as minimal CIDRs
+    pub unused: Vec<Cidr>,
+}
+
+/**
+VLSM planner: assigns each `(name, hosts_required)` requirement the smallest
+subnet with enough usable hosts (by the rules of [Cidr::hosts], so a
+2-host IPv4 point-to-point link gets a /31), inside a parent network.
+
+Subnets are placed largest first, each at the lowest free aligned address.
+As all sizes are powers of two, this never leaves holes between subnets:
+a plan fits exactly when the subnets' sizes add up to at most the parent's.
+*/
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Vlsm {
+    parent: Cidr,
+    max_prefix: Option<u8>,
+}
+
+impl Vlsm {
+    pub fn new(parent: Cidr) -> Self {
+        Self {
+            parent: parent.network(),
+            max_prefix: None,
+        }
+    }
+
+    /**
+    Never plan subnets longer than `/prefix`, however few hosts they need,
+    f.ex. `64` for one /64 per VLAN in IPv6.
+    */
+    pub fn with_max_prefix(mut self, prefix: u8) -> Self {
+        self.max_prefix = Some(prefix);
+        self
+    }
+
+    /// Plan subnets for `needs`. Requirements with equal sizes keep their order.
+    pub fn plan<S: AsRef<str>>(&self, needs: &[(S, u128)]) -> Result<VlsmPlan, VlsmError> {
+        let bits: u8 = match self.parent.addr {
+            IpAddr::V4(_) => IPV4_BITS,
+            IpAddr::V6(_) => IPV6_BITS,
+        };
+        let max_prefix: u8 = self.max_prefix.unwrap_or(bits).min(bits);
+
+        let mut sized: Vec<(&str, u128, u8)> = Vec::with_capacity(needs.len());
+        for (name, hosts) in needs {
+            let name: &str = name.as_ref();
+            if *hosts == 0 {
+                return Err(VlsmError::NoHosts(name.to_string()));
+            }
+            let Some(prefix) = prefix_for_hosts(bits, *hosts) else {
+                // more hosts than the whole address family holds
+                return Err(VlsmError::DoesNotFit {
+                    needed: hosts.saturating_add(reserved_hosts(bits)),
+                    available: self.parent.len(),
+                });
+            };
+            sized.push((name, *hosts, prefix.min(max_prefix)));
+        }
+        sized.sort_by_key(|(_, _, prefix)| *prefix);
+
+        let needed: u128 = sized
+            .iter()
+            .map(|(_, _, p)| block_len(bits, *p))
+            .fold(0, u128::saturating_add);
+        // also catches single subnets larger than the parent
+        let available: u128 = self.parent.len();
+        if needed > available {
+            return Err(VlsmError::DoesNotFit { needed, available });
+        }
+
+        let mut ipam: Ipam = Ipam::new(self.parent).with_strategy(FitStrategy::FirstFit);
+        let mut subnets: Vec<VlsmSubnet> = Vec::with_capacity(sized.len());
+        for (name, hosts_required, prefix) in sized {
+            let cidr: Cidr = ipam
+                .allocate(prefix)
+                .map_err(|_| VlsmError::DoesNotFit { needed, available })?;
+            subnets.push(VlsmSubnet {
+                name: name.to_string(),
+                hosts_required,
+                cidr,
+                hosts: cidr.info().hosts,
+            });
+        }
+        Ok(VlsmPlan {
+            parent: self.parent,
+            subnets,
+            unused: ipam.free(),
+        })
+    }
+}
+
+impl VlsmPlan {
+    /// Render the plan, unused space last, as a table for printing.
+    #[cfg(feature = "tabulate")]
+    pub fn to_table(&self) -> Vec<String> {
+        let planned = self.subnets.iter().map(|s| {
+            let info: CidrInfo = s.cidr.info();
+            [
+                s.name.clone(),
+                s.cidr.to_string(),
+                s.hosts_required.to_string(),
+                s.hosts.to_string(),
+                info.first_host.to_string(),
+                info.last_host.to_string(),
+            ]
+        });
+        let unused = self.unused.iter().map(|c| {
+            let info: CidrInfo = c.info();
+            [
+                UNUSED.to_string(),
+                c.to_string(),
+                "-".to_string(),
+                info.hosts.to_string(),
+                info.first_host.to_string(),
+                info.last_host.to_string(),
+            ]
+        });
+        simple_tabulate(
+            planned.chain(unused),
+            Some(&[
+                "name",
+                "subnet",
+                "required",
+                "usable",
+                "first host",
+                "last host",
+            ]),
+        )
+    }
+}
+
+/* ---------------------------------- */
+
+/**
+Longest prefix with at least `hosts` usable hosts: all addresses for the
+two longest prefixes, otherwise minus network and broadcast (IPv4) or the
+Subnet-Router anycast address (IPv6). None if not even `/0` has enough.
+*/
+fn prefix_for_hosts(bits: u8, hosts: u128) -> Option<u8> {
+    match hosts {
+        1 => Some(bits),
+        2 => Some(bits - 1),
+        _ => {
+            let len: u8 = ceil_log2(hosts.checked_add(reserved_hosts(bits))?);
+            bits.checked_sub(len)
+        }
+    }
+}
+
+/// Addresses of a block which are not usable as hosts (for prefixes < bits - 1).
+#[inline]
+fn reserved_hosts(bits: u8) -> u128 {
+    if bits == IPV4_BITS {
+        2
+    } else {
+        1
+    }
+}
+
+/// ceil(log2(x)) for x >= 1
+#[inline]
+fn ceil_log2(x: u128) -> u8 {
+    match x {
+        0 | 1 => 0,
+        _ => (128 - (x - 1).leading_zeros()) as u8,
+    }
+}
+
+/// Number of addresses in a `/prefix`, saturating.
+#[inline]
+fn block_len(bits: u8, prefix: u8) -> u128 {
+    1u128
+        .checked_shl((bits - prefix) as u32)
+        .unwrap_or(u128::MAX)
+}
+
+/* -------------------------------------------------------------------------- */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cidrs(plan: &VlsmPlan) -> Vec<String> {
+        plan.subnets.iter().map(|s| s.cidr.to_string()).collect()
+    }
+
+    #[test]
+    fn test_plan_v4() {
+        let mut needs: Vec<(String, u128)> =
+            vec![("C".into(), 12), ("A".into(), 500), ("B".into(), 120)];
+        needs.extend((1..=20).map(|i| (format!("link{i}"), 2)));
+
+        let plan: VlsmPlan = Vlsm::new("10.0.0.0/22".parse().unwrap())
+            .plan(&needs)
+            .unwrap();
+        assert_eq!(
+            cidrs(&plan)[..5],
+            [
+                "10.0.0.0/23",
+                "10.0.2.0/25",
+                "10.0.2.128/28",
+                "10.0.2.144/31",
+                "10.0.2.146/31"
+            ]
+        );
+        assert_eq!(plan.subnets[0].name, "A");
+        assert_eq!(plan.subnets[0].hosts, 510);
+        assert_eq!(plan.subnets[3].name, "link1");
+        assert_eq!(
+            plan.subnets.last().unwrap().cidr.to_string(),
+            "10.0.2.182/31"
+        );
+        let unused: Vec<String> = plan.unused.iter().map(|c| c.to_string()).collect();
+        assert_eq!(unused, ["10.0.2.184/29", "10.0.2.192/26", "10.0.3.0/24"]);
+
+        #[cfg(feature = "tabulate")]
+        {
+            let table: Vec<String> = plan.to_table();
+            assert_eq!(table.len(), 2 + needs.len() + unused.len());
+            assert!(table.last().unwrap().starts_with(UNUSED));
+        }
+    }
+
+    #[test]
+    fn test_plan_v6_and_errors() {
+        let needs: [(&str, u128); 3] = [("vlan10", 100), ("vlan20", 100), ("ptp", 2)];
+        let plan: VlsmPlan = Vlsm::new("2001:db8::/60".parse().unwrap())
+            .with_max_prefix(64)
+            .plan(&needs)
+            .unwrap();
+        assert_eq!(
+            cidrs(&plan),
+            ["2001:db8::/64", "2001:db8:0:1::/64", "2001:db8:0:2::/64"]
+        );
+        assert_eq!(plan.unused.len(), 3);
+        // without the cap, sizes follow the host counts
+        let plan: VlsmPlan = Vlsm::new("2001:db8::/119".parse().unwrap())
+            .plan(&needs)
+            .unwrap();
+        assert_eq!(
+            cidrs(&plan),
+            ["2001:db8::/121", "2001:db8::80/121", "2001:db8::100/127"]
+        );
+
+        assert_eq!(
+            Vlsm::new("10.0.0.0/24".parse().unwrap()).plan(&[("big", 300)]),
+            Err(VlsmError::DoesNotFit {
+                needed: 512,
+                available: 256
+            })
+        );
+        assert!(matches!(
+            Vlsm::new("10.0.0.0/24".parse().unwrap()).plan(&[("a", 200), ("b", 100)]),
+            Err(VlsmError::DoesNotFit { needed: 384, .. })
+        ));
+        assert_eq!(
+            Vlsm::new("10.0.0.0/24".parse().unwrap()).plan(&[("none", 0)]),
+            Err(VlsmError::NoHosts("none".into()))
+        );
+        // more hosts than the address family holds
+        let all: Vlsm = Vlsm::new("0.0.0.0/0".parse().unwrap());
+        assert_eq!(
+            all.plan(&[("x", 1 << 33)]),
+            Err(VlsmError::DoesNotFit {
+                needed: (1 << 33) + 2,
+                available: 1 << 32
+            })
+        );
+        assert_eq!(
+            all.plan(&[("x", u32::MAX as u128)]),
+            Err(VlsmError::DoesNotFit {
+                needed: (1 << 32) + 1,
+                available: 1 << 32
+            })
+        );
+        assert_eq!(prefix_for_hosts(IPV4_BITS, (1 << 32) - 2), Some(0));
+        assert_eq!(prefix_for_hosts(IPV4_BITS, (1 << 32) - 1), None);
+        assert_eq!(prefix_for_hosts(IPV6_BITS, u128::MAX), None);
+        assert_eq!(prefix_for_hosts(IPV4_BITS, 6), Some(29));
+        assert_eq!(prefix_for_hosts(IPV4_BITS, 7), Some(28));
+    }
+}
@@ -0,0 +1,189 @@
+// Copyright (c) 2026 Mikko Tanner. All rights reserved.
+// Licensed under the MIT License or the Apache License, Version 2.0.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! IPv6 zone identifiers (RFC 4007), f.ex. the `eth0` in `fe80::1%eth0`.
+
+use super::{prelude::*, strings::*, AddressError};
+use core::{cmp::Ordering, fmt, hash, net::IpAddr, str::FromStr};
+#[cfg(feature = "serde")]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// Longest accepted zone ID in bytes (Linux `IFNAMSIZ` minus the terminator).
+pub const ZONE_MAX_LEN: usize = 15;
+
+/**
+Zone (scope) identifier of a scoped IPv6 address: an interface name like
+`eth0` or an interface index like `2`.
+
+Stored inline so that [Cidr](super::Cidr) and [IpRange](super::IpRange)
+remain `Copy`. The zone is kept as given; `2` and the name of interface 2
+are *different* zones as far as this crate is concerned.
+*/
+#[derive(Clone, Copy)]
+pub struct ZoneId {
+    len: u8,
+    buf: [u8; ZONE_MAX_LEN],
+}
+
+impl ZoneId {
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        // only ever constructed from a valid &str, see FromStr
+        core::str::from_utf8(&self.buf[..self.len as usize]).expect("zone ID is valid UTF-8")
+    }
+}
+
+impl FromStr for ZoneId {
+    type Err = AddressError;
+
+    /// Zone IDs are 1..=[ZONE_MAX_LEN] bytes without whitespace, `%` or `/`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let valid: bool = !s.is_empty()
+            && s.len() <= ZONE_MAX_LEN
+            && !s.contains(|c: char| c.is_whitespace() || c == '%' || c == '/');
+        if !valid {
+            return Err(AddressError::InvalidZone(s.to_string()));
+        }
+        let mut buf: [u8; ZONE_MAX_LEN] = [0; ZONE_MAX_LEN];
+        buf[..s.len()].copy_from_slice(s.as_bytes());
+        Ok(ZoneId {
+            len: s.len() as u8,
+            buf,
+        })
+    }
+}
+
+impl fmt::Display for ZoneId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl fmt::Debug for ZoneId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ZoneId").field(&self.as_str()).finish()
+    }
+}
+
+impl PartialEq for ZoneId {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for ZoneId {}
+
+impl PartialOrd for ZoneId {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ZoneId {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl hash::Hash for ZoneId {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for ZoneId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for ZoneId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s: String = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+/* ---------------------------------- */
+
+/// Split `addr%zone` into its parts. Does not validate the address part.
+pub(crate) fn split_zone(s: &str) -> Result<(&str, Option<ZoneId>), AddressError> {
+    match s.split_once(PERCENT) {
+        Some((addr, zone)) => Ok((addr, Some(zone.parse()?))),
+        None => Ok((s, None)),
+    }
+}
+
+/// Parse an IP address with an optional zone. Zones are only valid for IPv6.
+pub(crate) fn parse_zoned_ip(s: &str) -> Result<(IpAddr, Option<ZoneId>), AddressError> {
+    let (addr, zone) = split_zone(s.trim())?;
+    let ip: IpAddr = addr
+        .parse()
+        .map_err(|_| AddressError::Invalid(s.to_string()))?;
+    check_zone(ip, zone, s)?;
+    Ok((ip, zone))
+}
+
+/// Error out if `ip` has a zone but is not IPv6. `input` is for the error message.
+#[inline]
+pub(crate) fn check_zone(
+    ip: IpAddr,
+    zone: Option<ZoneId>,
+    input: &str,
+) -> Result<(), AddressError> {
+    match (ip, zone) {
+        (IpAddr::V4(_), Some(_)) => Err(AddressError::InvalidZone(input.to_string())),
+        _ => Ok(()),
+    }
+}
+
+/**
+The common zone of two range endpoints: a zone given on only one of them
+applies to both, but two different zones are an error.
+*/
+pub(crate) fn merge_zones(
+    a: Option<ZoneId>,
+    b: Option<ZoneId>,
+) -> Result<Option<ZoneId>, AddressError> {
+    match (a, b) {
+        (Some(x), Some(y)) if x != y => Err(AddressError::ZoneMismatch(x, y)),
+        (x, y) => Ok(x.or(y)),
+    }
+}
+
+/* -------------------------------------------------------------------------- */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zone_parse() {
+        let z: ZoneId = "eth0".parse().unwrap();
+        assert_eq!(z.as_str(), "eth0");
+        assert_eq!(format!("{z:?}"), "ZoneId(\"eth0\")");
+        assert_eq!(z, "eth0".parse().unwrap());
+        assert!(z < "eth1".parse().unwrap());
+        assert!("br-lan".parse::<ZoneId>().is_ok());
+        assert!("123456789012345".parse::<ZoneId>().is_ok());
+
+        for bad in ["", "1234567890123456", "eth 0", "a%b", "a/b"] {
+            assert!(bad.parse::<ZoneId>().is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_zoned_ip() {
+        let (ip, zone) = parse_zoned_ip("fe80::1%2").unwrap();
+        assert_eq!(ip.to_string(), "fe80::1");
+        assert_eq!(zone.unwrap().as_str(), "2");
+        assert_eq!(parse_zoned_ip("fe80::1").unwrap().1, None);
+        assert!(matches!(
+            parse_zoned_ip("10.0.0.1%eth0"),
+            Err(AddressError::InvalidZone(_))
+        ));
+    }
+}
@@ -1,23 +1,60 @@
 // Copyright (c) 2024-2025 Mikko Tanner. All rights reserved.
 
+/*!
+Minimal utilities. The heavier subsystems sit behind cargo features, all
+enabled by default:
+
+- `sysinfo`: [SysInfo] with per-core [CpuStats], `procinfo`: [ProcessInfo]
+- `iptools`: the [iptools] module
+- `tabulate`: [simple_tabulate] and the `to_table` renderers of [iptools]
+- `regex`: the regex based parts of [iptools]
+- `serde`: `Serialize`/`Deserialize` for the data types
+- `cloud`: cloud provider range parsing in [iptools], with `serde_json`
+- `std`: everything else, implied by all of the above but `iptools` and `serde`
+
+Without `std` the crate is `no_std` and only needs `alloc`, which leaves
+[iptools] with its parsing, collapsing and set logic.
+*/
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+mod error;
+#[cfg(feature = "std")]
 mod filesystem;
+#[cfg(feature = "std")]
 mod humanbytes;
+#[cfg(feature = "iptools")]
 pub mod iptools;
+#[cfg(feature = "sysinfo")]
 mod minisysinfo;
+#[cfg(feature = "procinfo")]
 mod procinfo;
+#[cfg(feature = "std")]
 mod strtobytes;
+#[cfg(feature = "tabulate")]
 mod tabulator;
 
+use alloc::string::String;
+use core::fmt::{Debug, Display, Write};
+pub use error::Error;
+#[cfg(feature = "std")]
 pub use filesystem::check_readable_dir;
+#[cfg(feature = "std")]
 pub use humanbytes::HumanBytes;
-pub use minisysinfo::SysInfo;
+#[cfg(feature = "sysinfo")]
+pub use minisysinfo::{CpuStats, SysInfo};
+#[cfg(feature = "procinfo")]
 pub use procinfo::ProcessInfo;
+#[cfg(feature = "std")]
 use std::{
-    fmt::{Debug, Display, Write},
     path::{Component, Path, PathBuf},
     thread::available_parallelism,
 };
+#[cfg(feature = "std")]
 pub use strtobytes::{str_to_bytes, str_to_bytes_64};
+#[cfg(feature = "tabulate")]
 pub use tabulator::{simple_tabulate, tabulate_with_missing};
 
 static PLACEHOLDER: &str = "{}";
@@ -42,7 +79,7 @@ impl<T: Debug> ToDebug for T {
     /// Convert a value to its debug string. Convenience method for `format!("{self:?}")`.
     #[inline]
     fn to_debug(&self) -> String {
-        format!("{self:?}")
+        alloc::format!("{self:?}")
     }
 }
 
@@ -50,7 +87,7 @@ impl<T: Display> ToDisplay for T {
     /// Convert a value to its display string. Convenience method for `format!("{self}")`.
     #[inline]
     fn to_display(&self) -> String {
-        format!("{self}")
+        alloc::format!("{self}")
     }
 }
 
@@ -132,6 +169,7 @@ macro_rules! templater {
 /* ######################################################################### */
 
 /// Get the number of available CPUs, but at least 1.
+#[cfg(feature = "std")]
 pub fn num_cpus() -> usize {
     match available_parallelism() {
         Ok(available) => available.get(),
@@ -216,6 +254,7 @@ character [`U+FFFD REPLACEMENT CHARACTER`][U+FFFD].
 
 [U+FFFD]: core::char::REPLACEMENT_CHARACTER
 */
+#[cfg(feature = "std")]
 pub fn normalize_path<P>(path: P, strict: bool) -> PathBuf
 where P: AsRef<Path>,
 {
@@ -276,7 +315,7 @@ where P: AsRef<Path>,
 
 /* ######################################################################### */
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
 
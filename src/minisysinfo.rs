@@ -2,15 +2,19 @@
 
 use crate::HumanBytes as HuB;
 use parking_lot::RwLock;
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
-use sysinfo::{CpuRefreshKind, MemoryRefreshKind, RefreshKind, System};
+use sysinfo::{Cpu, CpuRefreshKind, MemoryRefreshKind, RefreshKind, System};
 use timesince::{SecondsSinceEpoch, TimeSinceEpoch};
 
 const MIN_INTERVAL: Duration = Duration::from_millis(200);
+/// Per-core usage levels for [SysInfo::cpus_str], from idle to fully busy.
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
 
 /// Static information about the system.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SysInfoStatic {
     pub hostname: String,
     pub os_name: String,
@@ -22,16 +26,20 @@ pub struct SysInfoStatic {
 }
 
 /// SysInfoDynamic contains the mutable system information.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SysInfoDynamic {
     pub mem: MemoryStats,
     pub cpu: f32,
+    /// per logical CPU, in the order reported by the OS
+    pub cpus: Vec<CpuStats>,
     pub load: (f32, f32, f32),
     pub when: TimeSinceEpoch,
 }
 
 /// Memory information for the system.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct MemoryStats {
     pub total: u64,
     pub free: u64,
@@ -42,6 +50,18 @@ pub struct MemoryStats {
     pub swap_used: u64,
 }
 
+/// Usage and current frequency of a single logical CPU.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CpuStats {
+    /// index of the logical CPU
+    pub id: usize,
+    /// usage as a percentage
+    pub usage: f32,
+    /// current frequency in MHz, 0 if unknown
+    pub freq: u64,
+}
+
 /// Information about the system.
 pub struct SysInfo {
     sys: RwLock<System>,
@@ -92,11 +112,21 @@ impl MemoryStats {
     }
 }
 
+impl CpuStats {
+    fn new(id: usize, cpu: &Cpu) -> Self {
+        Self {
+            id,
+            usage: cpu.cpu_usage(),
+            freq: cpu.frequency(),
+        }
+    }
+}
+
 impl SysInfo {
     pub fn new() -> Self {
         // Create a System object to query system information
         let kind = RefreshKind::nothing()
-            .with_cpu(CpuRefreshKind::everything().without_frequency())
+            .with_cpu(CpuRefreshKind::everything())
             .with_memory(MemoryRefreshKind::everything());
         let mut sys = System::new_with_specifics(kind);
         std::thread::sleep(MIN_INTERVAL); // must sleep for accurate initial data
@@ -107,6 +137,7 @@ impl SysInfo {
             inner: RwLock::new(SysInfoDynamic {
                 mem: MemoryStats::collect(&sys),
                 cpu: sys.global_cpu_usage(),
+                cpus: collect_cpus(&sys),
                 load: get_load_avg(),
                 when: TimeSinceEpoch::new(),
             }),
@@ -126,6 +157,7 @@ impl SysInfo {
         i.when = TimeSinceEpoch::new();
         i.mem.update(&sys);
         i.cpu = sys.global_cpu_usage();
+        i.cpus = collect_cpus(&sys);
         i.load = get_load_avg();
     }
 
@@ -170,6 +202,24 @@ impl SysInfo {
         self.inner.read().cpu
     }
 
+    /// Usage and frequency of each logical CPU.
+    ///
+    /// NOTE: system info is updated when calling this method.
+    pub fn cpus(&self) -> Vec<CpuStats> {
+        self.refresh();
+        self.inner.read().cpus.clone()
+    }
+
+    /**
+    The `n` busiest logical CPUs, busiest first. A single core near 100%
+    while [cpu](Self::cpu) stays low points to a single-threaded bottleneck.
+
+    NOTE: system info is updated when calling this method.
+    */
+    pub fn busiest(&self, n: usize) -> Vec<CpuStats> {
+        busiest_of(self.cpus(), n)
+    }
+
     /// Load averages for the last 1, 5, and 15 minutes.
     pub fn load(&self) -> (f32, f32, f32) {
         self.refresh();
@@ -198,25 +248,32 @@ impl SysInfo {
         format!("{:.2}%", self.cpu())
     }
 
+    /// Per-core CPU usage as one bar character per logical CPU, f.ex. "▁▁█▂".
+    pub fn cpus_str(&self) -> String {
+        cpu_bars(&self.cpus())
+    }
+
     /// Print the system information to stderr.
     ///
-    /// Format: `<ts> mem: 1 GiB used: 200 MiB avail: 800 MiB CPU: 5.55% load: 0.30 0.20 0.10`
+    /// Format: `<ts> mem: 1 GiB used: 200 MiB avail: 800 MiB CPU: 5.55% [▁▁█▂] load: 0.30 0.20 0.10`
     pub fn print(&self) {
         self.refresh();
         let i = self.inner.read();
         let (mem, avail) = (i.mem.total, i.mem.avail);
         let used = mem - avail;
         let cpu = i.cpu;
+        let bars = cpu_bars(&i.cpus);
         let (l1, l5, l15) = i.load;
         let ts = i.when.clone();
         drop(i); // release the read lock before printing
         eprintln!(
-            "{} | mem: {} used: {} avail: {} CPU: {:.2}% load: {:.2} {:.2} {:.2}",
+            "{} | mem: {} used: {} avail: {} CPU: {:.2}% [{}] load: {:.2} {:.2} {:.2}",
             ts,
             num2human(mem),
             num2human(used),
             num2human(avail),
             cpu,
+            bars,
             l1,
             l5,
             l15
@@ -232,8 +289,60 @@ fn num2human(num: u64) -> String {
     HuB::to_human(num as f64, false, 2).unwrap_or("0.0".to_string())
 }
 
+/// Collect usage and frequency of each logical CPU.
+fn collect_cpus(sys: &System) -> Vec<CpuStats> {
+    sys.cpus()
+        .iter()
+        .enumerate()
+        .map(|(id, cpu)| CpuStats::new(id, cpu))
+        .collect()
+}
+
+/// The `n` busiest of `cpus`, busiest first. Ties keep the CPU order.
+fn busiest_of(mut cpus: Vec<CpuStats>, n: usize) -> Vec<CpuStats> {
+    cpus.sort_by(|a, b| b.usage.total_cmp(&a.usage));
+    cpus.truncate(n);
+    cpus
+}
+
+/// Render per-core usage as [BARS], one character per CPU.
+fn cpu_bars(cpus: &[CpuStats]) -> String {
+    cpus.iter()
+        .map(|c| {
+            let level: f32 = c.usage.clamp(0.0, 100.0) / 100.0 * BARS.len() as f32;
+            BARS[(level as usize).min(BARS.len() - 1)]
+        })
+        .collect()
+}
+
 /// Get the system load averages.
 fn get_load_avg() -> (f32, f32, f32) {
     let load = System::load_average();
     (load.one as f32, load.five as f32, load.fifteen as f32)
 }
+
+/* -------------------------------------------------------------------------- */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cpus(usages: &[f32]) -> Vec<CpuStats> {
+        usages
+            .iter()
+            .enumerate()
+            .map(|(id, &usage)| CpuStats { id, usage, freq: 0 })
+            .collect()
+    }
+
+    #[test]
+    fn test_bars_and_busiest() {
+        let stats: Vec<CpuStats> = cpus(&[0.0, 12.5, 55.0, 100.0, 3.0, 100.0]);
+        assert_eq!(cpu_bars(&stats), "▁▂▅█▁█");
+        assert_eq!(cpu_bars(&[]), "");
+
+        let ids = |v: Vec<CpuStats>| v.iter().map(|c| c.id).collect::<Vec<usize>>();
+        assert_eq!(ids(busiest_of(stats.clone(), 3)), [3, 5, 2]);
+        assert_eq!(ids(busiest_of(stats, 10)).len(), 6);
+    }
+}
@@ -1,7 +1,7 @@
 // Copyright (c) 2023 Mikko Tanner. All rights reserved.
 
+use crate::Error;
 use std::collections::HashMap;
-use std::num::TryFromIntError;
 use lazy_static::lazy_static;
 
 // Lazily evaluated table of size constants.
@@ -74,11 +74,13 @@ Special cases:
 * `u128`: The number of bytes corresponding to the size specification.
 
 # Errors
+[Error::ByteSize], with the integer parse error as its source:
  * if the string cannot be parsed as a floating-point number
  * if it does not have a recognized suffix, or does not end with 'b' or 'byte'
  * if the number of bytes is too large to fit in a u128
 */
-pub fn str_to_bytes(size_str: &str) -> Result<u128, String> {
+pub fn str_to_bytes(size_str: &str) -> Result<u128, Error> {
+    let input: &str = size_str;
     let binding: String = size_str.to_lowercase();
     let size_str: &str = binding.trim().trim_end_matches('s');
 
@@ -100,16 +102,11 @@ pub fn str_to_bytes(size_str: &str) -> Result<u128, String> {
         &size_str
     };
 
-    match size_str.parse::<u128>().ok() {
-        Some(val) => {
-            if val > u128::MAX as u128 {
-                Err("number too large to fit in a u128".to_string())
-            } else {
-                Ok(val)
-            }
-        }
-        None => Err("string conversion to a number failed".to_string())
-    }
+    // u128 overflow is reported by the parse error itself
+    size_str.parse::<u128>().map_err(|source| Error::ByteSize {
+        input: input.to_string(),
+        source,
+    })
 }
 
 /**
@@ -123,8 +120,8 @@ of `u128`.
 * `s` - The string to be converted to bytes
 
 # Errors
-* Returns an error if the parsing is not successful.
-* Returns an error if the number of bytes is too large to fit in a u64.
+* The error of `str_to_bytes()` if the parsing is not successful.
+* [Error::ByteOverflow] if the number of bytes is too large to fit in a u64.
 
 # Example
 ```ignore
@@ -132,7 +129,7 @@ let bytes = str_to_bytes_64("64k").unwrap();
 assert_eq!(bytes, 65536);
 ```
 */
-pub fn str_to_bytes_64(s: &str) -> Result<u64, TryFromIntError> {
-    let val: u128 = str_to_bytes(s).unwrap_or(u128::MAX);
-    u64::try_from(val)
+pub fn str_to_bytes_64(s: &str) -> Result<u64, Error> {
+    let val: u128 = str_to_bytes(s)?;
+    u64::try_from(val).map_err(|_| Error::ByteOverflow(val))
 }